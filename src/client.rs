@@ -1,6 +1,8 @@
 // src/client.rs
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+use std::sync::Mutex;
 
 /// One client row from the sheet mapped into a strongly-typed struct.
 #[derive(Debug, Clone)]
@@ -66,6 +68,37 @@ impl Client {
         templates
     }
     
+    /// A column by its snake_case field name, for `{field}` prompt placeholders.
+    /// Y/N columns read as "Y"/"N".
+    pub fn field(&self, name: &str) -> Option<String> {
+        let yn = |b: bool| if b { "Y" } else { "N" }.to_string();
+        let v = match name {
+            "me" => self.me.clone(),
+            "returns_printed" => yn(self.returns_printed),
+            "returns_sent" => yn(self.returns_sent),
+            "client_id" => self.client_id.clone(),
+            "client_name" => self.client_name.clone(),
+            "email_temp1" => self.email_temp1.clone(),
+            "email_temp2" => self.email_temp2.clone(),
+            "comment" => self.comment.clone(),
+            "estimate_quarterlies" => self.estimate_quarterlies.clone(),
+            "tax_return" => self.tax_return.clone(),
+            "signature" => self.signature.clone(),
+            "signature_template" => self.signature_template.clone(),
+            "require_kba" => yn(self.require_kba),
+            "invoice" => self.invoice.clone(),
+            "invoice_amount" => self.invoice_amount.clone(),
+            "invoice_template" => self.invoice_template.clone(),
+            "closer" => self.closer.clone(),
+            "pipeline" => self.pipeline.clone(),
+            "seal" => self.seal.clone(),
+            "year_to_seal" => self.year_to_seal.clone(),
+            "row_index" => self.row_index.to_string(),
+            _ => return None,
+        };
+        Some(v.trim().to_string())
+    }
+
     pub fn est_qtr(&self) -> Vec<String> {
         let mut estimates = Vec::new();
         
@@ -80,10 +113,10 @@ impl Client {
 #[derive(Debug, Default)]
 pub struct ClientStore {
     pub clients: Vec<Client>,
-    pub seal_column_index: usize,   // 1-based column index for "Seal" column
+    pub seal_column_index: usize,   // 1-based column index for "Seal" column (0 if absent)
     pub me_column_index: usize,     // 1-based column index for "ME" column
-    pub email1_column_index: usize, // 1-based column index for "EmailTemp1" column
-    pub email2_column_index: usize, // 1-based column index for "EmailTemp2" column
+    pub email1_column_index: usize, // 1-based column index for "EmailTemp1" column (0 if absent)
+    pub email2_column_index: usize, // 1-based column index for "EmailTemp2" column (0 if absent)
 }
 
 #[allow(dead_code)]
@@ -110,9 +143,12 @@ impl ClientStore {
     /// ]
     ///
     /// We:
-    /// - read headers from the first row;
+    /// - read headers from the first row (only ME, ClientID, ClientName are required;
+    ///   any other missing column reads as an empty string);
     /// - iterate rows until the first with empty "ME";
-    /// - only include rows where "ME" is non-empty.
+    /// - only include rows where "ME" is non-empty;
+    /// - skip rows whose "Comment" contains a SKIP_COMMENT_MARKERS marker (logged);
+    /// - keep only ONLY_CLIENTS / drop SKIP_CLIENTS client IDs when those are set.
     pub fn from_sheet_values(values: &[Vec<String>]) -> Result<Self> {
         if values.is_empty() {
             return Ok(Self::new());
@@ -121,43 +157,50 @@ impl ClientStore {
         let header = &values[0];
 
         // Helper to find a column index by exact header text.
+        let optional_idx = |name: &str| -> Option<usize> {
+            header.iter().position(|h| h.trim() == name)
+        };
         let idx = |name: &str| -> Result<usize> {
-            header
-                .iter()
-                .position(|h| h.trim() == name)
-                .ok_or_else(|| anyhow!("Missing expected header '{name}'"))
+            optional_idx(name).ok_or_else(|| anyhow!("Missing expected header '{name}'"))
         };
 
+        // Only these are required; every other column defaults to "" when absent.
         let c_me = idx("ME")?;
-        let c_returns_printed = idx("Returns Printed?")?;
-        let c_returns_sent = idx("Returns Sent?")?;
         let c_client_id = idx("ClientID")?;
         let c_client_name = idx("ClientName")?;
-        let c_email1 = idx("EmailTemp1")?;
-        let c_email2 = idx("EmailTemp2")?;
-        let c_comment = idx("Comment")?;
-        let c_estimate = idx("Estimate/Quarterlies")?;
-        let c_tax_return = idx("TaxReturn")?;
-        let c_signature = idx("Signature")?;
-        let c_sig_tmpl = idx("SignatureTemplate")?;
-        let c_require_kba = idx("RequireKBA")?;
-        let c_invoice = idx("Invoice")?;
-        let c_invoice_amount = idx("InvoiceAmount")?;
-        let c_invoice_tmpl = idx("InvoiceTemplate")?;
-        let c_closer = idx("Closer")?;
-        let c_pipeline = idx("Pipeline")?;
-        let c_seal = idx("Seal")?;
-        let c_year_to_seal = idx("YearToSeal")?;
+
+        let c_returns_printed = optional_idx("Returns Printed?");
+        let c_returns_sent = optional_idx("Returns Sent?");
+        let c_email1 = optional_idx("EmailTemp1");
+        let c_email2 = optional_idx("EmailTemp2");
+        let c_comment = optional_idx("Comment");
+        let c_estimate = optional_idx("Estimate/Quarterlies");
+        let c_tax_return = optional_idx("TaxReturn");
+        let c_signature = optional_idx("Signature");
+        let c_sig_tmpl = optional_idx("SignatureTemplate");
+        let c_require_kba = optional_idx("RequireKBA");
+        let c_invoice = optional_idx("Invoice");
+        let c_invoice_amount = optional_idx("InvoiceAmount");
+        let c_invoice_tmpl = optional_idx("InvoiceTemplate");
+        let c_closer = optional_idx("Closer");
+        let c_pipeline = optional_idx("Pipeline");
+        let c_seal = optional_idx("Seal");
+        let c_year_to_seal = optional_idx("YearToSeal");
         let mut store = ClientStore {
             clients: Vec::new(),
-            seal_column_index: c_seal + 1,     // Convert 0-based to 1-based
-            me_column_index: c_me + 1,         // Convert 0-based to 1-based
-            email1_column_index: c_email1 + 1, // Convert 0-based to 1-based
-            email2_column_index: c_email2 + 1, // Convert 0-based to 1-based
+            // Convert 0-based to 1-based; 0 means the column is absent
+            seal_column_index: c_seal.map_or(0, |c| c + 1),
+            me_column_index: c_me + 1,
+            email1_column_index: c_email1.map_or(0, |c| c + 1),
+            email2_column_index: c_email2.map_or(0, |c| c + 1),
         };
 
+        let markers = skip_comment_markers();
+        let only = client_id_list("ONLY_CLIENTS");
+        let skip = client_id_list("SKIP_CLIENTS");
+
         for (row_idx, row) in values.iter().skip(1).enumerate() {
-            let me = get_cell(row, c_me);
+            let me = get_cell(row, Some(c_me));
             // Stop at first empty ME (your rule).
             if me.trim().is_empty() {
                 break;
@@ -170,8 +213,8 @@ impl ClientStore {
                 me,
                 returns_printed: parse_yn(&get_cell(row, c_returns_printed)),
                 returns_sent: parse_yn(&get_cell(row, c_returns_sent)),
-                client_id: get_cell(row, c_client_id),
-                client_name: get_cell(row, c_client_name),
+                client_id: get_cell(row, Some(c_client_id)),
+                client_name: get_cell(row, Some(c_client_name)),
                 email_temp1: get_cell(row, c_email1),
                 email_temp2: get_cell(row, c_email2),
                 comment: get_cell(row, c_comment),
@@ -190,12 +233,46 @@ impl ClientStore {
                 row_index: sheet_row,
             };
 
+            let id = client.client_id.to_uppercase();
+            if (!only.is_empty() && !only.contains(&id)) || skip.contains(&id) {
+                continue;
+            }
+            if let Some(marker) = skip_marker_in(&client.comment, &markers) {
+                println!(
+                    "⏭️ Skipping row {sheet_row} ({}): comment {:?} contains {marker:?}",
+                    client.client_name, client.comment
+                );
+                continue;
+            }
+
             store.clients.push(client);
         }
 
+        if !only.is_empty() || !skip.is_empty() {
+            println!(
+                "🎯 client filter (ONLY_CLIENTS={only:?}, SKIP_CLIENTS={skip:?}): {} client(s) kept",
+                store.clients.len()
+            );
+        }
         Ok(store)
     }
 
+    /// Clients whose returns are printed but not yet sent.
+    pub fn pending_to_send(&self) -> Vec<&Client> {
+        self.clients
+            .iter()
+            .filter(|c| c.returns_printed && !c.returns_sent)
+            .collect()
+    }
+
+    /// Clients with an invoice amount whose "Invoice" column isn't marked done (Y) yet.
+    pub fn needs_invoice(&self) -> Vec<&Client> {
+        self.clients
+            .iter()
+            .filter(|c| !parse_yn(&c.invoice) && !c.invoice_amount.trim().is_empty())
+            .collect()
+    }
+
     /// Convenience: clear and reload into an existing store.
     pub fn reload_from_sheet(&mut self, values: &[Vec<String>]) -> Result<()> {
         *self = ClientStore::from_sheet_values(values)?;
@@ -203,14 +280,146 @@ impl ClientStore {
     }
 }
 
+/// The client whose steps are running (set at each BeginClient), for prompt templating.
+static CURRENT_CLIENT: Mutex<Option<Client>> = Mutex::new(None);
+
+pub fn set_current_client(client: Option<Client>) {
+    *CURRENT_CLIENT.lock().unwrap() = client;
+}
+
+/// Fill `{field}` placeholders (see `Client::field`) from the current client. Only
+/// `{snake_case}` counts as a placeholder, so other braces pass through; an unknown
+/// field, or a placeholder outside any client, is an error rather than a literal prompt.
+pub fn render_prompt(template: &str) -> Result<String> {
+    if !template.contains('{') {
+        return Ok(template.to_string());
+    }
+    let current = CURRENT_CLIENT.lock().unwrap();
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let name = after.find('}').map(|close| &after[..close]).filter(|n| {
+            !n.is_empty() && n.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        });
+        let Some(name) = name else {
+            out.push('{');
+            rest = after;
+            continue;
+        };
+        let Some(client) = current.as_ref() else {
+            bail!("prompt {template:?} uses {{{name}}} but no client is active (before any BeginClient?)");
+        };
+        match client.field(name) {
+            Some(v) => out.push_str(&v),
+            None => bail!("prompt {template:?}: unknown client field {{{name}}}"),
+        }
+        rest = &after[name.len() + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Non-empty "Comment" cells by 1-based sheet row, for tagging a client's log lines.
+pub fn comments_by_row(values: &[Vec<String>]) -> HashMap<usize, String> {
+    let Some(header) = values.first() else { return HashMap::new() };
+    let Some(c_comment) = header.iter().position(|h| h.trim() == "Comment") else {
+        return HashMap::new();
+    };
+    values
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, row)| (i + 1, get_cell(row, Some(c_comment))))
+        .filter(|(_, c)| !c.is_empty())
+        .collect()
+}
+
 /* ---------- Small internal helpers ---------- */
 
-fn get_cell(row: &[String], idx: usize) -> String {
-    row.get(idx)
+/// SKIP_COMMENT_MARKERS: comma-separated, case-insensitive (default "HOLD,SKIP").
+fn skip_comment_markers() -> Vec<String> {
+    std::env::var("SKIP_COMMENT_MARKERS")
+        .unwrap_or_else(|_| "HOLD,SKIP".to_string())
+        .split(',')
+        .map(|m| m.trim().to_uppercase())
+        .filter(|m| !m.is_empty())
+        .collect()
+}
+
+/// Comma-separated client IDs from `key`, uppercased (empty when unset).
+fn client_id_list(key: &str) -> Vec<String> {
+    std::env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(|id| id.trim().to_uppercase())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// First marker present in `comment` as a whole word (or run of words, for multi-word
+/// markers), case-insensitively: "HOLD" matches "on hold" but not "household". A marker
+/// right after a negation ("don't skip", "do not skip", "no hold") doesn't count.
+fn skip_marker_in<'a>(comment: &str, markers: &'a [String]) -> Option<&'a str> {
+    let words: Vec<String> = comment
+        .replace('’', "'")
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .map(|w| w.trim_matches('\'').to_uppercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let negated = |i: usize| {
+        i > 0 && matches!(words[i - 1].as_str(), "DON'T" | "DONT" | "NOT" | "NO" | "NEVER")
+    };
+    markers
+        .iter()
+        .find(|m| {
+            let marker: Vec<&str> = m.split_whitespace().collect();
+            !marker.is_empty()
+                && words
+                    .windows(marker.len())
+                    .enumerate()
+                    .any(|(i, w)| w.iter().zip(&marker).all(|(a, b)| a == b) && !negated(i))
+        })
+        .map(|m| m.as_str())
+}
+
+fn get_cell(row: &[String], idx: Option<usize>) -> String {
+    idx.and_then(|i| row.get(i))
         .map(|s| s.trim().to_string())
         .unwrap_or_default()
 }
 
 fn parse_yn(s: &str) -> bool {
     matches!(s.trim().to_ascii_uppercase().as_str(), "Y" | "YES" | "TRUE" | "1")
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers() -> Vec<String> {
+        vec!["HOLD".to_string(), "SKIP".to_string(), "NOT READY".to_string()]
+    }
+
+    #[test]
+    fn marker_matches_whole_words_case_insensitively() {
+        assert_eq!(skip_marker_in("on hold until May", &markers()), Some("HOLD"));
+        assert_eq!(skip_marker_in("Skip, client moved", &markers()), Some("SKIP"));
+        assert_eq!(skip_marker_in("docs not ready yet", &markers()), Some("NOT READY"));
+    }
+
+    #[test]
+    fn marker_inside_a_longer_word_is_ignored() {
+        assert_eq!(skip_marker_in("household income changed", &markers()), None);
+        assert_eq!(skip_marker_in("shareholder K-1 pending", &markers()), None);
+        assert_eq!(skip_marker_in("skipped estimates last year", &markers()), None);
+    }
+
+    #[test]
+    fn negated_marker_is_ignored() {
+        assert_eq!(skip_marker_in("don't skip, priority client", &markers()), None);
+        assert_eq!(skip_marker_in("Do NOT skip", &markers()), None);
+        assert_eq!(skip_marker_in("don’t skip", &markers()), None);
+        assert_eq!(skip_marker_in("no hold this year", &markers()), None);
+    }
+}
@@ -0,0 +1,73 @@
+// src/console.rs
+//
+// One stdin reader for every operator prompt (RESUME pauses, SAFE_MODE "yes"). Lines
+// are read on a single plain thread and handed out over a channel, so a prompt that
+// finished another way (e.g. the RESUME file) never leaves a stray read_line behind
+// to swallow the answer to the next prompt.
+//
+// Time spent waiting on the operator is tracked (`operator_pause`), so the per-step
+// watchdog can leave it out.
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+
+/// Finished operator pauses, plus the start of the one in progress (if any).
+static PAUSED: std::sync::Mutex<(Duration, Option<Instant>)> = std::sync::Mutex::new((Duration::ZERO, None));
+
+/// Run `wait` (a prompt or pause for a person) with its duration counted as operator time.
+/// The pause is closed even if the surrounding step is dropped mid-wait.
+pub async fn operator_pause<F: Future>(wait: F) -> F::Output {
+    struct Closing;
+    impl Drop for Closing {
+        fn drop(&mut self) {
+            let mut paused = PAUSED.lock().unwrap();
+            if let Some(started) = paused.1.take() {
+                paused.0 += started.elapsed();
+            }
+        }
+    }
+
+    PAUSED.lock().unwrap().1 = Some(Instant::now());
+    let _closing = Closing;
+    wait.await
+}
+
+/// Total time spent in `operator_pause` so far, including a pause still in progress.
+pub fn operator_paused_total() -> Duration {
+    let paused = PAUSED.lock().unwrap();
+    paused.0 + paused.1.map_or(Duration::ZERO, |s| s.elapsed())
+}
+
+fn lines() -> &'static Mutex<mpsc::UnboundedReceiver<String>> {
+    static LINES: OnceLock<Mutex<mpsc::UnboundedReceiver<String>>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        let (tx, rx) = mpsc::unbounded_channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            // EOF (no terminal) or a read error closes the channel
+            match std::io::stdin().read_line(&mut line) {
+                Ok(n) if n > 0 => {
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        });
+        Mutex::new(rx)
+    })
+}
+
+/// Drop lines typed before the current prompt was shown.
+pub async fn discard_pending_lines() {
+    let mut rx = lines().lock().await;
+    while rx.try_recv().is_ok() {}
+}
+
+/// Next line the operator enters; None once stdin is closed. Cancel-safe: a prompt that
+/// stops waiting leaves the line for the next prompt.
+pub async fn next_line() -> Option<String> {
+    lines().lock().await.recv().await
+}
@@ -25,6 +25,9 @@ pub struct NormalizationInputs {
     pub window_y: i32,
     pub window_w: i32,
     pub window_h: i32,
+    /// window.devicePixelRatio at capture time. The point and the screenshot size are both
+    /// divided by this before the window scale is applied; use 1.0 when unknown.
+    pub device_pixel_ratio: f64,
 }
  
 pub fn viewport_to_screen(
@@ -37,23 +40,33 @@ pub fn viewport_to_screen(
         return (inputs.window_x, inputs.window_y);
     }
 
+    // HiDPI: the point and the screenshot size are in device px; convert both to CSS px
+    // with the same factor so the point keeps its fractional position.
+    let dpr = if inputs.device_pixel_ratio.is_finite() && inputs.device_pixel_ratio > 0.0 {
+        inputs.device_pixel_ratio
+    } else {
+        1.0
+    };
+    let css_w = inputs.screenshot_w as f64 / dpr;
+    let css_h = inputs.screenshot_h as f64 / dpr;
+
     // We assume the rendered overlay stretches the *viewport screenshot* to fill the window width.
     // That implies an anisotropic scale (by width). Whatever vertical space remains is the header strip.
     
-    let sx = inputs.window_w as f64 / inputs.screenshot_w as f64;
-    let sy = inputs.window_h as f64 / inputs.screenshot_h as f64;
+    let sx = inputs.window_w as f64 / css_w;
+    let sy = inputs.window_h as f64 / css_h;
     let scale = sx.min(sy);
 
-    let drawn_w = (inputs.screenshot_w as f64 * scale).round();
-    let drawn_h = (inputs.screenshot_h as f64 * scale).round();
+    let drawn_w = (css_w * scale).round();
+    let drawn_h = (css_h * scale).round();
 
     // Centered paddings
     let pad_x = ((inputs.window_w as f64 - drawn_w) / 2.0).round() as i32;
     let pad_y = ((inputs.window_h as f64 - drawn_h) / 2.0).round() as i32;
 
     // Map screenshot-space → centered image in the window
-    let mut dx = ((x_view as f64) * scale).round() as i32;
-    let mut dy = ((y_view as f64) * scale).round() as i32;
+    let mut dx = (x_view as f64 / dpr * scale).round() as i32;
+    let mut dy = (y_view as f64 / dpr * scale).round() as i32;
 
     // Clamp inside drawn image
     dx = dx.clamp(0, drawn_w as i32 - 1);
@@ -73,3 +86,44 @@ pub fn viewport_to_screen(
     ) 
     
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(dpr: f64) -> NormalizationInputs {
+        NormalizationInputs {
+            screenshot_w: (1000.0 * dpr.max(1.0)) as i32,
+            screenshot_h: (800.0 * dpr.max(1.0)) as i32,
+            window_x: 0,
+            window_y: 0,
+            window_w: 1000,
+            window_h: 800,
+            device_pixel_ratio: dpr,
+        }
+    }
+
+    #[test]
+    fn centre_stays_centred_at_ratio_1_and_2() {
+        for dpr in [1.0, 2.0] {
+            let i = inputs(dpr);
+            let centre = viewport_to_screen(i, i.screenshot_w / 2, i.screenshot_h / 2);
+            assert_eq!(centre, (500, 400), "dpr {dpr}");
+        }
+    }
+
+    #[test]
+    fn fractional_position_is_preserved_at_ratio_2() {
+        let i = inputs(2.0);
+        // A quarter of the way across a 2000x1600 device-px shot → a quarter of the window
+        assert_eq!(viewport_to_screen(i, 500, 400), (250, 200));
+        assert_eq!(viewport_to_screen(inputs(1.0), 250, 200), (250, 200));
+    }
+
+    #[test]
+    fn unknown_ratio_falls_back_to_1() {
+        let one = viewport_to_screen(inputs(1.0), 321, 123);
+        assert_eq!(viewport_to_screen(inputs(0.0), 321, 123), one);
+        assert_eq!(viewport_to_screen(inputs(f64::NAN), 321, 123), one);
+    }
+}
@@ -15,21 +15,38 @@ pub struct DriverBundle {
     pub chromedriver_child: Child,
     pub user_data_dir: PathBuf,
     pub display: String,
+    /// Running with --headless=new (ALLOW_HEADLESS=1); xdotool steps are skipped.
+    pub headless: bool,
+    /// PERSIST_PROFILE=1: `user_data_dir` is a stable profile that cleanup must keep.
+    pub persist_profile: bool,
+}
+ 
+/// True when HEADFUL is not 1 but ALLOW_HEADLESS=1 permits a headless (CI) session.
+pub fn headless_requested() -> bool {
+    let headful = env::var("HEADFUL").map_or(true, |v| v == "1");
+    !headful && env::var("ALLOW_HEADLESS").map_or(false, |v| v == "1")
 }
  
 pub async fn init_driver(_login_url: &str) -> Result<DriverBundle> {
     let _ = dotenvy::dotenv();
  
     let headful = env::var("HEADFUL").map_or(true, |v| v == "1");
-    if !headful {
-        bail!("OS-level cursor requires headful mode/VNC. Set HEADFUL=1.");
+    let headless = headless_requested();
+    if !headful && !headless {
+        bail!("OS-level cursor requires headful mode/VNC. Set HEADFUL=1 (or ALLOW_HEADLESS=1 for DOM-only runs).");
     }
  
     let display = env::var("DISPLAY_VNC").unwrap_or_else(|_| String::from(":1"));
-    let driver_port: u16 = env::var("CHROMEDRIVER_PORT")
+    let requested_port: u16 = env::var("CHROMEDRIVER_PORT")
         .ok()
         .and_then(|s| s.parse().ok())
         .unwrap_or(9515);
+    // A leftover chromedriver (or anything else) on the port would answer our WebDriver
+    // connect with its own session state; move to the next free port instead.
+    let driver_port = first_free_port(requested_port)?;
+    if driver_port != requested_port {
+        println!("⚠️ port {requested_port} is already in use; starting chromedriver on {driver_port}");
+    }
  
     let chromedriver_path =
         which("chromedriver").context("chromedriver not found in PATH. Install it or add to PATH.")?;
@@ -38,14 +55,14 @@ pub async fn init_driver(_login_url: &str) -> Result<DriverBundle> {
  
     let log_file = File::create(log_path()).context("cannot create chromedriver.log")?;
  
-    let chromedriver = spawn_chromedriver(
+    let mut chromedriver = spawn_chromedriver(
         chromedriver_path.as_path(),
         driver_port,
         &display,
         xauth.as_deref(),
         log_file,
     )?;
-    wait_for_port("127.0.0.1", driver_port, Duration::from_secs(10))
+    wait_for_port("127.0.0.1", driver_port, &mut chromedriver, Duration::from_secs(10))
         .context("chromedriver did not become ready on time")?;
  
     // ---- Build Chrome caps (WINDOWED) ----
@@ -57,10 +74,21 @@ pub async fn init_driver(_login_url: &str) -> Result<DriverBundle> {
         caps.set_binary(&bin)?;
     }
  
-    // Fresh profile per run
-    let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
-    let mut user_data_dir = env::temp_dir();
-    user_data_dir.push(format!("interactive-webdriver-{}", timestamp_ms));
+    // Fresh profile per run, unless PERSIST_PROFILE=1 keeps cookies/logins in a stable dir
+    let persist_profile = env::var("PERSIST_PROFILE").map_or(false, |v| v == "1");
+    let user_data_dir = if persist_profile {
+        let dir = persistent_profile_dir();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create profile dir {}", dir.display()))?;
+        ensure_profile_unlocked(&dir)?;
+        println!("🍪 PERSIST_PROFILE=1: reusing Chrome profile at {}", dir.display());
+        dir
+    } else {
+        let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let mut dir = env::temp_dir();
+        dir.push(format!("interactive-webdriver-{}", timestamp_ms));
+        dir
+    };
     caps.add_arg(&format!("--user-data-dir={}", user_data_dir.to_string_lossy()))?;
  
     // IMPORTANT: windowed, not fullscreen. Keep device scale stable.
@@ -87,6 +115,10 @@ pub async fn init_driver(_login_url: &str) -> Result<DriverBundle> {
     caps.add_arg("--disable-save-password-bubble")?;
     caps.add_arg("--disable-autofill")?;
     caps.add_arg("--disable-features=AutofillSaveCardBubble,PasswordManagerOnboarding")?;    
+    if headless {
+        println!("🕶️ ALLOW_HEADLESS=1: launching Chrome headless; xdotool steps will be skipped");
+        caps.add_arg("--headless=new")?;
+    }
     
 
     caps.add_experimental_option("excludeSwitches", vec!["enable-automation"])?;
@@ -98,19 +130,172 @@ pub async fn init_driver(_login_url: &str) -> Result<DriverBundle> {
         "autofill.credit_card_enabled": false
     }))?;
 
+    // On a cold VNC the first session request can fail even though chromedriver is
+    // listening; retry a few times, restarting chromedriver if it went away.
     let driver_url = format!("http://127.0.0.1:{driver_port}");
-    let driver = WebDriver::new(&driver_url, caps).await?;
+    const SESSION_ATTEMPTS: u32 = 3;
+    let mut attempt = 0;
+    let driver = loop {
+        attempt += 1;
+        match WebDriver::new(&driver_url, caps.clone()).await {
+            Ok(d) => break d,
+            // Retrying can't fix a version mismatch; say what to update instead
+            Err(e) if version_mismatch(&e.to_string()).is_some() => {
+                let _ = chromedriver.kill();
+                bail!("{}", version_mismatch(&e.to_string()).unwrap_or_default());
+            }
+            Err(e) if attempt < SESSION_ATTEMPTS => {
+                let wait = Duration::from_millis(500 * attempt as u64);
+                eprintln!(
+                    "⚠️ WebDriver session failed (attempt {attempt}/{SESSION_ATTEMPTS}): {e}; retrying in {}ms",
+                    wait.as_millis()
+                );
+                tokio::time::sleep(wait).await;
+
+                if chromedriver.try_wait()?.is_some() {
+                    eprintln!("🔁 chromedriver exited; restarting it on port {driver_port}");
+                    // Append so the crashed instance's output is still there afterwards
+                    let log_file = std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(log_path())
+                        .context("cannot open chromedriver.log")?;
+                    chromedriver = spawn_chromedriver(
+                        chromedriver_path.as_path(),
+                        driver_port,
+                        &display,
+                        xauth.as_deref(),
+                        log_file,
+                    )?;
+                    wait_for_port("127.0.0.1", driver_port, &mut chromedriver, Duration::from_secs(10))
+                        .context("restarted chromedriver did not become ready on time")?;
+                }
+            }
+            Err(e) => {
+                let _ = chromedriver.kill();
+                return Err(e).context(format!("could not create a WebDriver session after {SESSION_ATTEMPTS} attempts"));
+            }
+        }
+    };
+    verify_viewport(&driver).await;
    
     Ok(DriverBundle {
         driver,
         chromedriver_child: chromedriver,
         user_data_dir,
         display,
+        headless,
+        persist_profile,
     })
 }
+
+/// window.innerWidth/innerHeight in CSS pixels; None (with a warning) if unreadable.
+async fn read_viewport(driver: &WebDriver) -> Option<(i64, i64)> {
+    match driver
+        .execute("return [window.innerWidth, window.innerHeight]", Vec::new())
+        .await
+    {
+        Ok(ret) => ret.json().as_array().and_then(|a| {
+            Some((a.first()?.as_i64()?, a.get(1)?.as_i64()?))
+        }),
+        Err(e) => {
+            eprintln!("⚠️ could not read the browser viewport size: {e}");
+            None
+        }
+    }
+}
+
+/// Resize the Chrome window (outer size, position kept) and re-cache the resulting
+/// viewport in VIEWPORT_W/VIEWPORT_H so later heuristics and coordinate math use it.
+/// Returns the new viewport size.
+pub async fn set_window_size(driver: &WebDriver, width: u32, height: u32) -> Result<(i64, i64)> {
+    let rect = driver.get_window_rect().await?;
+    driver
+        .set_window_rect(rect.x.max(0) as u32, rect.y.max(0) as u32, width, height)
+        .await
+        .with_context(|| format!("could not resize the window to {width}x{height}"))?;
+
+    // Let responsive layouts reflow before anything measures the page
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let (w, h) = read_viewport(driver)
+        .await
+        .context("window resized but the new viewport size could not be read")?;
+    env::set_var("VIEWPORT_W", w.to_string());
+    env::set_var("VIEWPORT_H", h.to_string());
+    Ok((w, h))
+}
+
+/// Chrome may not honour --window-size exactly (DPI, WM decorations), and the DOM
+/// heuristic scores distance from the viewport center using VIEWPORT_W/VIEWPORT_H.
+/// Read the real inner size and, if it differs, warn and update those env values.
+async fn verify_viewport(driver: &WebDriver) {
+    let Some((w, h)) = read_viewport(driver).await else { return };
+
+    let want_w: i64 = env::var("VIEWPORT_W").ok().and_then(|s| s.parse().ok()).unwrap_or(1280);
+    let want_h: i64 = env::var("VIEWPORT_H").ok().and_then(|s| s.parse().ok()).unwrap_or(800);
+    if (w, h) != (want_w, want_h) {
+        println!("⚠️ viewport is {w}x{h}, not VIEWPORT_W/H {want_w}x{want_h}; using the actual size");
+        env::set_var("VIEWPORT_W", w.to_string());
+        env::set_var("VIEWPORT_H", h.to_string());
+    } else {
+        println!("📐 viewport {w}x{h}");
+    }
+}
+
+/// PROFILE_DIR, or $HOME/.return-automater/profile.
+fn persistent_profile_dir() -> PathBuf {
+    if let Ok(dir) = env::var("PROFILE_DIR") {
+        return PathBuf::from(dir);
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".return-automater").join("profile")
+}
+
+/// Chrome holds `SingletonLock` (a symlink to "<host>-<pid>") while a profile is open.
+/// A live owner means another run is using it; a dead owner's lock is stale and removed.
+fn ensure_profile_unlocked(dir: &Path) -> Result<()> {
+    let lock = dir.join("SingletonLock");
+    let Ok(target) = std::fs::read_link(&lock) else {
+        return Ok(()); // no lock
+    };
+
+    let owner_pid = target
+        .to_string_lossy()
+        .rsplit('-')
+        .next()
+        .and_then(|p| p.parse::<u32>().ok());
+    match owner_pid {
+        Some(pid) if !Path::new(&format!("/proc/{pid}")).exists() => {
+            println!("🧹 Removing stale Chrome profile lock (pid {pid} is gone)");
+            std::fs::remove_file(&lock).context("cannot remove stale SingletonLock")?;
+            Ok(())
+        }
+        _ => bail!(
+            "Chrome profile {} is in use ({} -> {}). Another run may be active; stop it or unset PERSIST_PROFILE.",
+            dir.display(),
+            lock.display(),
+            target.display()
+        ),
+    }
+}
  
-pub async fn screenshot_bytes(driver: &WebDriver, path: &str) -> Result<(String, Vec<u8>)> {
-    let png = driver.screenshot_as_png().await?;
+/// Capture a PNG, save it to `path` (suffixed -001, -002... if taken) and return both.
+/// `full_page` captures the whole document via DevTools (Page.captureScreenshot with
+/// captureBeyondViewport) for questions about content below the fold; clicks must use
+/// viewport captures, since their coordinates map to the visible area.
+pub async fn screenshot_bytes(driver: &WebDriver, path: &str, full_page: bool) -> Result<(String, Vec<u8>)> {
+    let png = if full_page {
+        match full_page_png(driver).await {
+            Ok(png) => png,
+            Err(e) => {
+                eprintln!("⚠️ full-page capture failed ({e:#}); falling back to the viewport");
+                driver.screenshot_as_png().await?
+            }
+        }
+    } else {
+        driver.screenshot_as_png().await?
+    };
 
     let mut target = std::path::PathBuf::from(path);
     if let Some(dir) = target.parent() {
@@ -140,10 +325,277 @@ pub async fn screenshot_bytes(driver: &WebDriver, path: &str) -> Result<(String,
 
 
  
+/// Whole-document PNG via CDP: the capture is clipped to the page's CSS content size.
+async fn full_page_png(driver: &WebDriver) -> Result<Vec<u8>> {
+    use base64::Engine;
+    use thirtyfour::extensions::cdp::ChromeDevTools;
+
+    let dev = ChromeDevTools::new(driver.handle.clone());
+    // Without a clip, captureBeyondViewport still returns just the viewport
+    let metrics = dev.execute_cdp("Page.getLayoutMetrics").await?;
+    let size = &metrics["cssContentSize"];
+    let (width, height) = (size["width"].as_f64(), size["height"].as_f64());
+    let (Some(width), Some(height)) = (width, height) else {
+        bail!("Page.getLayoutMetrics returned no cssContentSize");
+    };
+    let ret = dev
+        .execute_cdp_with_params(
+            "Page.captureScreenshot",
+            json!({
+                "format": "png",
+                "captureBeyondViewport": true,
+                "fromSurface": true,
+                "clip": { "x": 0, "y": 0, "width": width, "height": height, "scale": 1 },
+            }),
+        )
+        .await?;
+    let data = ret["data"].as_str().context("Page.captureScreenshot returned no data")?;
+    Ok(base64::engine::general_purpose::STANDARD.decode(data)?)
+}
+
+/// Read window.devicePixelRatio from the page; 1.0 if the script fails or returns junk.
+pub async fn device_pixel_ratio(driver: &WebDriver) -> f64 {
+    match driver.execute("return window.devicePixelRatio", Vec::new()).await {
+        Ok(ret) => ret
+            .json()
+            .as_f64()
+            .filter(|r| r.is_finite() && *r > 0.0)
+            .unwrap_or(1.0),
+        Err(_) => 1.0,
+    }
+}
+
+/// Browser zoom as devicePixelRatio rounded to 2 places; Chrome runs with
+/// --force-device-scale-factor=1, so anything but 1.0 is page zoom. None if the script fails.
+pub async fn zoom_level(driver: &WebDriver) -> Option<f64> {
+    let ret = driver
+        .execute("return Math.round(window.devicePixelRatio * 100) / 100", Vec::new())
+        .await
+        .ok()?;
+    ret.json().as_f64().filter(|z| z.is_finite() && *z > 0.0)
+}
+
+/// Poll for a CSS selector until it resolves or `timeout` elapses.
+/// Returns how long the wait took.
+pub async fn wait_for_element(
+    driver: &WebDriver,
+    selector: &str,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<Duration> {
+    let start = std::time::Instant::now();
+    loop {
+        if driver.find(By::Css(selector)).await.is_ok() {
+            return Ok(start.elapsed());
+        }
+        if start.elapsed() >= timeout {
+            bail!("element '{}' did not appear within {:?}", selector, timeout);
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
+
+/// Poll until nothing matching `selector` is displayed (absent, hidden, or detached
+/// mid-check) or `timeout` elapses — the inverse of `wait_for_element`, for spinners.
+/// Returns how long the wait took; an invalid selector or dead session is an error.
+pub async fn wait_for_hidden(
+    driver: &WebDriver,
+    selector: &str,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<Duration> {
+    let start = std::time::Instant::now();
+    loop {
+        // find_all is empty when nothing matches; an error is a bad selector or a dead
+        // session, which must not read as "hidden"
+        let elements = driver
+            .find_all(By::Css(selector))
+            .await
+            .with_context(|| format!("could not query '{selector}'"))?;
+        let mut visible = false;
+        for el in elements {
+            // An element that went stale (or can't report) mid-poll is gone, not displayed
+            if el.is_displayed().await.unwrap_or(false) {
+                visible = true;
+                break;
+            }
+        }
+        if !visible {
+            return Ok(start.elapsed());
+        }
+        if start.elapsed() >= timeout {
+            bail!("element '{}' was still displayed after {:?}", selector, timeout);
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
+
+/// One-shot check: is `substring` in the `scope` element's text, or the page source?
+/// A missing scope element counts as "not contained".
+pub async fn page_contains(driver: &WebDriver, substring: &str, scope: Option<&str>) -> bool {
+    let haystack = match scope {
+        Some(sel) => match driver.find(By::Css(sel)).await {
+            Ok(el) => el.text().await.unwrap_or_default(),
+            Err(_) => String::new(),
+        },
+        None => driver.source().await.unwrap_or_default(),
+    };
+    haystack.contains(substring)
+}
+
+/// Poll until `substring` is (`present`) or is not (`!present`) in the page: the text of
+/// the `scope` element when given, otherwise the page source. Returns how long it took.
+pub async fn wait_for_text(
+    driver: &WebDriver,
+    substring: &str,
+    present: bool,
+    scope: Option<&str>,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<Duration> {
+    let start = std::time::Instant::now();
+    loop {
+        if page_contains(driver, substring, scope).await == present {
+            return Ok(start.elapsed());
+        }
+        if start.elapsed() >= timeout {
+            let where_ = scope.map_or_else(|| "page".to_string(), |s| format!("'{s}'"));
+            if present {
+                bail!("text {:?} did not appear in {} within {:?}", substring, where_, timeout);
+            }
+            bail!("text {:?} was still in {} after {:?}", substring, where_, timeout);
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
+
+/// Poll `current_url` until navigation has happened; returns the new URL and the wait.
+/// Done when the URL no longer contains `from_contains` (or, without it, differs from the
+/// URL at call time) and, if given, contains `to_contains`. With `to_contains` alone, a
+/// URL that already matches counts, so a click that navigated instantly is not missed.
+pub async fn wait_for_url_change(
+    driver: &WebDriver,
+    from_contains: Option<&str>,
+    to_contains: Option<&str>,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<(String, Duration)> {
+    let start = std::time::Instant::now();
+    let start_url = driver.current_url().await?.to_string();
+    loop {
+        let url = driver.current_url().await?.to_string();
+        let left = match from_contains {
+            Some(from) => !url.contains(from),
+            None => to_contains.is_some() || url != start_url,
+        };
+        if left && to_contains.map_or(true, |to| url.contains(to)) {
+            return Ok((url, start.elapsed()));
+        }
+        if start.elapsed() >= timeout {
+            bail!(
+                "URL did not change (from {:?} to {:?}) within {:?}; still at {}",
+                from_contains.unwrap_or(&start_url),
+                to_contains.unwrap_or("anything else"),
+                timeout,
+                url
+            );
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
+
+/// Poll the Resource Timing buffer until no new entry has been added for `idle`
+/// (with `document.readyState == "complete"`). Returns how long it took. Requests still in
+/// flight don't show up until they finish, so `idle` should exceed a typical API call.
+pub async fn wait_for_network_idle(
+    driver: &WebDriver,
+    idle: Duration,
+    timeout: Duration,
+    poll: Duration,
+) -> Result<Duration> {
+    // The default buffer (250 entries) fills up on long-lived SPAs and then stops growing
+    let _ = driver
+        .execute("performance.setResourceTimingBufferSize(10000);", Vec::new())
+        .await;
+
+    let start = std::time::Instant::now();
+    let mut last_count: Option<u64> = None;
+    let mut quiet_since = std::time::Instant::now();
+    loop {
+        let ret = driver
+            .execute(
+                "return [performance.getEntriesByType('resource').length, document.readyState];",
+                Vec::new(),
+            )
+            .await?;
+        let (count, ready): (u64, String) =
+            serde_json::from_value(ret.json().clone()).unwrap_or((0, String::new()));
+
+        if last_count != Some(count) || ready != "complete" {
+            last_count = Some(count);
+            quiet_since = std::time::Instant::now();
+        } else if quiet_since.elapsed() >= idle {
+            return Ok(start.elapsed());
+        }
+        if start.elapsed() >= timeout {
+            bail!(
+                "network never went idle for {:?} within {:?} ({} resource entries, readyState {})",
+                idle, timeout, count, ready
+            );
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
+
+/// Best-effort session check. LOGGED_IN_SELECTOR (a marker only shown to signed-in users)
+/// wins when set; otherwise we are logged in unless a login form is on the page
+/// (LOGIN_FORM_SELECTOR, default a password input).
+pub async fn is_logged_in(driver: &WebDriver) -> bool {
+    if let Ok(marker) = env::var("LOGGED_IN_SELECTOR") {
+        if !marker.trim().is_empty() {
+            return driver.find(By::Css(marker.as_str())).await.is_ok();
+        }
+    }
+    let login_form = env::var("LOGIN_FORM_SELECTOR")
+        .unwrap_or_else(|_| "input[type='password']".to_string());
+    driver.find(By::Css(login_form.as_str())).await.is_err()
+}
+
+/// Fraction (0.0–1.0) of pixels that differ between two PNG screenshots.
+/// Compared on a ~160px-wide grayscale thumbnail so it is cheap enough to run per click.
+/// Undecodable input or a size change counts as fully changed.
+pub fn page_changed(before: &[u8], after: &[u8]) -> f64 {
+    const THUMB_W: u32 = 160;
+    const PIXEL_DELTA: u8 = 16; // ignore anti-aliasing / cursor blink noise
+
+    let (a, b) = match (image::load_from_memory(before), image::load_from_memory(after)) {
+        (Ok(a), Ok(b)) => (a, b),
+        _ => return 1.0,
+    };
+    if a.width() != b.width() || a.height() != b.height() || a.width() == 0 {
+        return 1.0;
+    }
+
+    let thumb_h = ((a.height() as u64 * THUMB_W as u64) / a.width() as u64).max(1) as u32;
+    let ga = image::imageops::thumbnail(&a.to_luma8(), THUMB_W, thumb_h);
+    let gb = image::imageops::thumbnail(&b.to_luma8(), THUMB_W, thumb_h);
+
+    let total = ga.as_raw().len().max(1);
+    let differing = ga
+        .as_raw()
+        .iter()
+        .zip(gb.as_raw())
+        .filter(|(x, y)| x.abs_diff(**y) > PIXEL_DELTA)
+        .count();
+    differing as f64 / total as f64
+}
+
 pub async fn cleanup_driver(bundle: &mut DriverBundle) {
     let _ = bundle.driver.clone().quit().await;
     let _ = bundle.chromedriver_child.kill();
-    let _ = std::fs::remove_dir_all(&bundle.user_data_dir);
+    if !bundle.persist_profile {
+        let _ = std::fs::remove_dir_all(&bundle.user_data_dir);
+    }
 }
  
 fn spawn_chromedriver(
@@ -165,9 +617,50 @@ fn spawn_chromedriver(
     Ok(child)
 }
  
-fn wait_for_port(host: &str, port: u16, timeout: Duration) -> Result<()> {
+/// Recognise chromedriver's "only supports Chrome version N" failure, in the session
+/// error or in chromedriver.log, and turn it into an actionable message.
+fn version_mismatch(err: &str) -> Option<String> {
+    let log = std::fs::read_to_string(log_path()).unwrap_or_default();
+    let text = if err.contains("only supports Chrome version") { err } else { log.as_str() };
+    let supported = number_after(text, "only supports Chrome version ")?;
+    let browser = number_after(text, "Current browser version is ").unwrap_or_else(|| "unknown".to_string());
+    Some(format!(
+        "chromedriver/Chrome version mismatch: chromedriver supports Chrome {supported}, \
+         but the browser is {browser}. Install the chromedriver matching your Chrome \
+         (https://googlechromelabs.github.io/chrome-for-testing/) or pin CHROME_BIN to a matching build."
+    ))
+}
+
+/// The dotted version number right after the last occurrence of `marker` in `text`.
+fn number_after(text: &str, marker: &str) -> Option<String> {
+    let rest = &text[text.rfind(marker)? + marker.len()..];
+    let v: String = rest.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let v = v.trim_end_matches('.');
+    (!v.is_empty()).then(|| v.to_string())
+}
+
+/// First port at or above `start` (checking up to 20) that nothing is listening on.
+fn first_free_port(start: u16) -> Result<u16> {
+    for port in start..start.saturating_add(20) {
+        if TcpStream::connect(("127.0.0.1", port)).is_err() {
+            return Ok(port);
+        }
+    }
+    bail!("no free chromedriver port in {}..{}", start, start.saturating_add(20))
+}
+
+/// Wait until `port` accepts connections, as long as `child` is still running: the port
+/// was free before spawning, so an exited child means something else grabbed it (or
+/// chromedriver failed to start) rather than our process being ready.
+fn wait_for_port(host: &str, port: u16, child: &mut Child, timeout: Duration) -> Result<()> {
     let start = std::time::Instant::now();
     while start.elapsed() < timeout {
+        if let Some(status) = child.try_wait()? {
+            bail!(
+                "chromedriver exited ({status}) before opening {host}:{port}; see {}",
+                log_path().display()
+            );
+        }
         if TcpStream::connect((host, port)).is_ok() {
             return Ok(());
         }
@@ -206,7 +699,38 @@ fn guess_xauthority() -> Result<Option<PathBuf>> {
     Ok(None)
 }
  
+/// chromedriver's verbose log, kept with the run's other artifacts.
 fn log_path() -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home).join("chromedriver.log")
+    crate::openai_client::artifact_path("chromedriver.log")
+}
+
+/// The last `n` lines of this run's chromedriver log (empty if there is none yet).
+/// Looks under RUN_DIR only, so a failure before any run folder exists doesn't create one.
+/// Only the end of the file is read (the verbose log grows large), and bytes that aren't
+/// UTF-8 are replaced rather than losing the whole tail.
+pub fn chromedriver_log_tail(n: usize) -> Vec<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let Ok(run_dir) = env::var("RUN_DIR") else {
+        return Vec::new();
+    };
+    let Ok(mut file) = File::open(Path::new(&run_dir).join("chromedriver.log")) else {
+        return Vec::new();
+    };
+    // ~1 KiB per verbose line, capped at 4 MiB
+    let window = (n as u64).saturating_mul(1024).min(4 << 20);
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = len.saturating_sub(window);
+    let mut buf = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_to_end(&mut buf).is_err() {
+        return Vec::new();
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if start > 0 && !lines.is_empty() {
+        // Starts mid-line
+        lines.remove(0);
+    }
+    lines[lines.len().saturating_sub(n)..].iter().map(|l| l.to_string()).collect()
 }
@@ -1,11 +1,21 @@
 // src/keyboard.rs
 use anyhow::{bail, Context, Result};
 use std::process::Command;
+use crate::mouse::{dry_run_enabled, jitter_between};
 
 
 /// Type literal text into the active window on the given DISPLAY.
 /// `per_char_delay_ms` is the inter-key delay (e.g., 6–15ms).
-pub fn type_text(display: &str, text: &str, per_char_delay_ms: u64) -> Result<()> {
+/// With HUMANIZE_TYPING=1 each keystroke's delay varies by ±HUMANIZE_JITTER_PCT (default 50)%.
+pub async fn type_text(display: &str, text: &str, per_char_delay_ms: u64) -> Result<()> {
+    if dry_run_enabled() {
+        println!("[DRY_RUN] would type {} chars", text.chars().count());
+        return Ok(());
+    }
+    if std::env::var("HUMANIZE_TYPING").map_or(false, |v| v == "1") {
+        return type_text_humanized(display, text, per_char_delay_ms).await;
+    }
+
     let status = Command::new("xdotool")
         .env("DISPLAY", display)
         .args([
@@ -25,27 +35,87 @@ pub fn type_text(display: &str, text: &str, per_char_delay_ms: u64) -> Result<()
     Ok(())
 }
 
+/// One xdotool call per character with a randomized pause between keystrokes.
+async fn type_text_humanized(display: &str, text: &str, per_char_delay_ms: u64) -> Result<()> {
+    let pct: u64 = std::env::var("HUMANIZE_JITTER_PCT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(50)
+        .min(100);
+    let spread = per_char_delay_ms * pct / 100;
+    let (lo, hi) = (per_char_delay_ms - spread, per_char_delay_ms + spread);
+
+    let mut buf = [0u8; 4];
+    for ch in text.chars() {
+        let status = Command::new("xdotool")
+            .env("DISPLAY", display)
+            .args(["type", "--clearmodifiers", "--delay", "0", "--", ch.encode_utf8(&mut buf)])
+            .status()
+            .context("xdotool type failed")?;
+        if !status.success() {
+            bail!("xdotool type returned non-zero status");
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(jitter_between(lo, hi))).await;
+    }
+    Ok(())
+}
+
 /// Press one or more keys synchronously using xdotool.
-/// Supports both single keys (e.g. "Return") and combinations like "ctrl+shift+p".
+/// Supports single keys (e.g. "Return"), chords like "ctrl+shift+p" (via `xdotool_chord`),
+/// and comma-separated sequences like "ctrl+a, Delete" (sent in one xdotool call).
 pub fn xdotool_key(display: &str, key: &str) -> Result<()> {
-    // Split key combos like "ctrl+shift+p" or "Ctrl + Alt + Delete"
-    let combo = key
-        .split(|c| c == '+' || c == ' ')
-        .filter(|s| !s.is_empty())
+    let chords: Vec<Vec<&str>> = key.split(',').map(chord_keys).filter(|c| !c.is_empty()).collect();
+
+    match chords.as_slice() {
+        [] => bail!("no keys given in {key:?}"),
+        [single] => xdotool_chord(display, single),
+        many => send_key_specs(display, &many.iter().map(|c| chord_spec(c)).collect::<Vec<_>>()),
+    }
+}
+
+/// Press a single modifier chord, e.g. `["ctrl", "shift", "k"]` → `ctrl+shift+k`.
+pub fn xdotool_chord(display: &str, keys: &[&str]) -> Result<()> {
+    let combo = chord_spec(keys);
+    if combo.is_empty() {
+        bail!("empty key chord");
+    }
+    send_key_specs(display, &[combo])
+}
+
+/// Split key combos like "ctrl+shift+p" or "Ctrl + Alt + Delete" into their key names.
+fn chord_keys(chord: &str) -> Vec<&str> {
+    chord.split(['+', ' ']).filter(|s| !s.is_empty()).collect()
+}
+
+/// Key names → xdotool's combined spec, e.g. `["Ctrl", "Alt", "delete"]` → "ctrl+alt+Delete".
+fn chord_spec(keys: &[&str]) -> String {
+    keys.iter()
+        .filter(|k| !k.trim().is_empty())
         .map(|k| normalize_key_name(k))
         .collect::<Vec<_>>()
-        .join("+");
+        .join("+")
+}
+
+/// One `xdotool key --clearmodifiers <spec>...` invocation.
+fn send_key_specs(display: &str, specs: &[String]) -> Result<()> {
+    let joined = specs.join(" ");
+
+    if dry_run_enabled() {
+        println!("[DRY_RUN] would press key combo: {joined}");
+        return Ok(());
+    }
 
-    println!("[TypeKey] Pressing key combo: {combo}");
+    println!("[TypeKey] Pressing key combo: {joined}");
 
     let status = Command::new("xdotool")
         .env("DISPLAY", display)
-        .args(["key", "--clearmodifiers", &combo])
+        .args(["key", "--clearmodifiers"])
+        .args(specs)
         .status()
         .context("xdotool key failed")?;
 
     if !status.success() {
-        bail!("xdotool key failed for combo: {combo}");
+        bail!("xdotool key failed for combo: {joined}");
     }
 
     Ok(())
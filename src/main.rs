@@ -1,3 +1,5 @@
+#[macro_use]
+mod steplog;
 mod openai_client;
 mod mouse;
 mod coords;
@@ -6,28 +8,104 @@ mod plan;
 mod overlay;
 mod keyboard;
 mod creds;
+mod otp;
+mod secrets;
+mod profile;
 mod client;
+mod console;
 mod sheets;
+mod plan_builder;
+mod plan_loader;
 
 use anyhow::{Context, Result};
 use openai_client::{
-    OpenAIConfig, ViewportPoint, call_openai_for_point, click_by_llm_dom_first,
+    OpenAIConfig, ViewportPoint, call_openai_for_point_cached, evict_last_cached_point, click_by_llm_dom_first, click_in_row,
+    hover_by_llm_dom_first, click_by_text,
     click_checkbox_for_row, click_options_menu_for_row, click_template_input,
     click_invoice_amount_input, click_sidebar_create_button, click_stage_option,
-    ask_boolean_question, get_largest_run_dir
+    ask_boolean_question, artifact_path, confirm_destructive, confirm_unlabelled_click, get_largest_run_dir, llm_requests_made,
+    select_dropdown_option, OpenAiError,
+};
+use driver::{
+    init_driver, cleanup_driver, device_pixel_ratio, is_logged_in,
+    page_changed, page_contains, screenshot_bytes, wait_for_element, wait_for_text,
+    set_window_size, wait_for_hidden, wait_for_network_idle, wait_for_url_change, zoom_level
 };
-use driver::{init_driver, cleanup_driver, screenshot_bytes};
 use mouse::{
-    ensure_xdotool, reset_zoom, get_active_window_geometry,
-    get_display_geometry, xdotool_move_and_click
+    ensure_xdotool, focus_chrome_window, reset_zoom, get_display_geometry, capture_display_png,
+    display_screenshot_source,
+    get_window_or_display_geometry, xdotool_drag, xdotool_move_and_click
 };
 use coords::{png_dimensions, NormalizationInputs, viewport_to_screen};
-use plan::{AutomationPlan, Step, fetch_keeper_creds_sync};
+use plan::{AutomationPlan, Step};
+use clap::Parser;
 use tokio::time::{sleep, Duration};
 use keyboard::type_text;
-use thirtyfour::By;
-use sheets::SheetsClient;
+use thirtyfour::{By, WebDriver};
+use sheets::{CellStatus, SheetsClient};
 use std::fs;
+use steplog::{RunReport, StepDetails, StepRecord, StepResult, ValidationRecord};
+
+/// Command-line overrides for the most common knobs. Precedence is
+/// CLI flag > --profile settings > env var (incl. .env) > built-in default; each flag is
+/// applied by setting its env var so the rest of the code keeps reading config from env.
+#[derive(Parser, Debug)]
+#[command(about = "Plan-driven browser automation over a Google Sheet of clients")]
+struct Cli {
+    /// Named profile from config.toml to apply (PROFILE)
+    #[arg(long)]
+    profile: Option<String>,
+    /// Login page to open first (LOGIN_URL)
+    #[arg(long)]
+    login_url: Option<String>,
+    /// JSON/YAML plan to run instead of the built-in client loop (PLAN_FILE)
+    #[arg(long)]
+    plan_file: Option<String>,
+    /// Run Chrome with a visible window (HEADFUL=1)
+    #[arg(long)]
+    headful: bool,
+    /// OpenAI model name (OPENAI_MODEL)
+    #[arg(long)]
+    model: Option<String>,
+    /// Log clicks/typing instead of performing them (DRY_RUN=1)
+    #[arg(long)]
+    dry_run: bool,
+    /// A1 range to read client rows from (SHEETS_RANGE)
+    #[arg(long)]
+    sheets_range: Option<String>,
+    /// Only run these client IDs, comma-separated (ONLY_CLIENTS)
+    #[arg(long, value_name = "IDS")]
+    only_clients: Option<String>,
+    /// Skip these client IDs, comma-separated (SKIP_CLIENTS)
+    #[arg(long, value_name = "IDS")]
+    skip_clients: Option<String>,
+    /// Re-aggregate the saved LLM dotmap samples in DIR with every strategy and exit
+    #[arg(long, value_name = "DIR")]
+    replay: Option<std::path::PathBuf>,
+    /// Click a generated target page and print recommended CLICK_X/Y_OFFSET_PX, then exit
+    #[arg(long)]
+    calibrate: bool,
+}
+
+impl Cli {
+    fn apply_to_env(&self) {
+        let pairs = [
+            ("LOGIN_URL", self.login_url.clone()),
+            ("PLAN_FILE", self.plan_file.clone()),
+            ("HEADFUL", self.headful.then(|| "1".to_string())),
+            ("OPENAI_MODEL", self.model.clone()),
+            ("DRY_RUN", self.dry_run.then(|| "1".to_string())),
+            ("SHEETS_RANGE", self.sheets_range.clone()),
+            ("ONLY_CLIENTS", self.only_clients.clone()),
+            ("SKIP_CLIENTS", self.skip_clients.clone()),
+        ];
+        for (key, value) in pairs {
+            if let Some(v) = value {
+                std::env::set_var(key, v);
+            }
+        }
+    }
+}
 
 /// Control-flow signals for automation
 #[derive(Debug)]
@@ -54,27 +132,735 @@ impl From<anyhow::Error> for ControlFlowError {
     }
 }
 
-/// Execute a single automation step
+/// CLICK_BACKEND=webdriver clicks through WebDriver actions; anything else (default)
+/// maps to screen coordinates and clicks with xdotool. SCREENSHOT_SOURCE=display always
+/// uses xdotool: its points can lie outside the page.
+fn use_webdriver_clicks() -> bool {
+    std::env::var("CLICK_BACKEND").map_or(false, |v| v.eq_ignore_ascii_case("webdriver"))
+        && !display_screenshot_source()
+}
+
+/// Zoom drifts (a stray ctrl+scroll) and silently skews the screenshot → screen math, so
+/// vision clicks first check it is 100%, send ctrl+0 if not, and re-check.
+/// ZOOM_CHECK=0 disables; skipped headless (no xdotool to send the key). `via_xdotool`
+/// is false for CLICK_BACKEND=webdriver clicks, which must not need xdotool: those only warn.
+async fn ensure_default_zoom(bundle: &driver::DriverBundle, display: &str, via_xdotool: bool) -> Result<()> {
+    if bundle.headless || std::env::var("ZOOM_CHECK").map_or(false, |v| v == "0") {
+        return Ok(());
+    }
+    let Some(zoom) = zoom_level(&bundle.driver).await else {
+        return Ok(());
+    };
+    if (zoom - 1.0).abs() < 0.01 {
+        return Ok(());
+    }
+    if !via_xdotool {
+        log_warn!("⚠️ Browser zoom is {:.0}%; clicks may land off target (ctrl+0 or a ResetZoom step fixes it)", zoom * 100.0);
+        return Ok(());
+    }
+
+    log_warn!("⚠️ Browser zoom is {:.0}%; resetting to 100% before clicking", zoom * 100.0);
+    // ctrl+0 goes to the focused window
+    if let Err(e) = focus_chrome_window(display) {
+        log_warn!("⚠️ Could not focus Chrome window: {e:#}");
+    }
+    reset_zoom(display)?;
+    sleep(Duration::from_millis(300)).await;
+    match zoom_level(&bundle.driver).await {
+        Some(z) if (z - 1.0).abs() >= 0.01 => anyhow::bail!(
+            "browser zoom is still {:.0}% after ctrl+0; click coordinates would be off",
+            z * 100.0
+        ),
+        _ => log_info!("🔍 Browser zoom reset to 100%"),
+    }
+    Ok(())
+}
+
+/// Screenshot for the vision path: the browser viewport, or the whole X display with
+/// SCREENSHOT_SOURCE=display. Saved to `path` like `screenshot_bytes`.
+async fn vision_screenshot(bundle: &driver::DriverBundle, display: &str, path: &str) -> Result<(String, Vec<u8>)> {
+    if !display_screenshot_source() {
+        return screenshot_bytes(&bundle.driver, path, false).await;
+    }
+    let png = capture_display_png(display)?;
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, &png)?;
+    log_info!("📸 Saved display capture to {path}");
+    Ok((path.to_string(), png))
+}
+
+/// `screenshot_point_to_screen` for a `vision_screenshot`: display captures are already
+/// in screen pixels, so only the clamp applies.
+fn vision_point_to_screen(
+    display: &str,
+    size: (u32, u32),
+    dpr: f64,
+    pt: ViewportPoint,
+) -> Result<(i32, i32)> {
+    if !display_screenshot_source() {
+        return screenshot_point_to_screen(display, size, dpr, pt);
+    }
+    let (dw, dh) = get_display_geometry(display)?;
+    Ok((pt.x.clamp(0, dw - 1), pt.y.clamp(0, dh - 1)))
+}
+
+/// Screenshot the viewport, ask OpenAI for a point, and click it
+/// (OS-level via xdotool, or WebDriver actions when CLICK_BACKEND=webdriver).
+/// Returns the chosen point in screenshot pixels.
+async fn click_by_llm_screenshot(
+    bundle: &mut driver::DriverBundle,
+    display: &str,
+    cfg: &OpenAIConfig,
+    prompt: &str,
+    double: Option<bool>,
+    focus_rect: Option<(u32, u32, u32, u32)>,
+) -> Result<ViewportPoint> {
+    ensure_default_zoom(bundle, display, !use_webdriver_clicks()).await?;
+    let shot = artifact_path("screenshot.png");
+    let (path, bytes) = vision_screenshot(bundle, display, &shot.to_string_lossy()).await?;
+    let (sw, sh) = png_dimensions(&bytes)?;
+    let page_url = bundle.driver.current_url().await?.to_string();
+    let dpr = device_pixel_ratio(&bundle.driver).await;
+
+    let mut pt: ViewportPoint =
+        call_openai_for_point_cached(cfg, &bytes, prompt, &page_url, focus_rect).await?;
+    if let Some(force) = double {
+        pt.double = force;
+    }
+
+    // SAFE_MODE: the model only gave a point; check what control is under it. A label
+    // that can't be read (display captures may point outside the page, where there is
+    // no DOM to ask) needs the same confirmation as a dangerous one.
+    let label = if display_screenshot_source() {
+        None
+    } else {
+        control_at_point(&bundle.driver, pt, dpr).await.and_then(|c| c.label)
+    };
+    match label {
+        Some(label) => confirm_destructive(&label).await?,
+        None => confirm_unlabelled_click(&format!("at ({}, {})", pt.x, pt.y)).await?,
+    }
+
+    if use_webdriver_clicks() {
+        // Screenshot pixels → CSS pixels relative to the viewport
+        let x = (pt.x as f64 / dpr).round() as i64;
+        let y = (pt.y as f64 / dpr).round() as i64;
+
+        if mouse::dry_run_enabled() {
+            log_info!("[DRY_RUN] [webdriver] would click viewport ({x}, {y}) double={}", pt.double);
+        } else {
+            let chain = bundle.driver.action_chain().move_to(x, y);
+            let chain = if pt.double { chain.double_click() } else { chain.click() };
+            chain.perform().await?;
+            log_info!("🖱️ [webdriver] clicked viewport ({x}, {y}) double={}", pt.double);
+        }
+    } else {
+        let (sx, sy) = vision_point_to_screen(display, (sw, sh), dpr, pt)?;
+        xdotool_move_and_click(display, sx, sy, pt.double)?;
+        log_info!("🖱️ [xdotool] clicked screen ({sx}, {sy}) double={}", pt.double);
+    }
+
+    let _ = fs::remove_file(path);
+    Ok(pt)
+}
+
+/// The clickable control under a screenshot-pixel point: its nearest
+/// button/link/input ancestor, else the element itself.
+struct ControlAtPoint {
+    /// Text + aria-label; None for an iframe/canvas/embed (no DOM text to check) or an
+    /// empty label.
+    label: Option<String>,
+    /// Bounding box in screenshot pixels; None when it covers most of the viewport
+    /// (a container such as body or a full-page wrapper, not the control).
+    rect: Option<(u32, u32, u32, u32)>,
+}
+
+/// None if nothing is under the point or the script fails.
+async fn control_at_point(driver: &WebDriver, pt: ViewportPoint, dpr: f64) -> Option<ControlAtPoint> {
+    let x = pt.x as f64 / dpr;
+    let y = pt.y as f64 / dpr;
+    let ret = driver
+        .execute(
+            "const el = document.elementFromPoint(arguments[0], arguments[1]);\
+             if (!el) return null;\
+             const opaque = ['IFRAME','FRAME','CANVAS','EMBED','OBJECT'].includes(el.tagName);\
+             const c = opaque ? el : (el.closest('button,a,[role=button],[role=link],input,[role=menuitem]') || el);\
+             const label = opaque ? '' : [c.innerText || c.value || '', c.getAttribute('aria-label') || ''].join(' ').trim();\
+             const r = c.getBoundingClientRect();\
+             const huge = r.width * r.height > 0.5 * window.innerWidth * window.innerHeight;\
+             return { label: label || null, rect: huge ? null : [r.left, r.top, r.width, r.height] };",
+            vec![serde_json::json!(x), serde_json::json!(y)],
+        )
+        .await
+        .ok()?;
+    let found = ret.json();
+    if found.is_null() {
+        return None;
+    }
+
+    let label = found["label"].as_str().map(|s| s.chars().take(200).collect());
+    let px = |v: f64| (v.max(0.0) * dpr).round() as u32;
+    let rect = serde_json::from_value::<Vec<f64>>(found["rect"].clone())
+        .ok()
+        .filter(|r| r.len() == 4 && r[2] > 0.0 && r[3] > 0.0)
+        .map(|r| (px(r[0]), px(r[1]), px(r[2]), px(r[3])));
+    Some(ControlAtPoint { label, rect })
+}
+
+/// DOCS_DIR (default docs/shots)/step-NN-<caption slug>.png for AnnotateShot.
+fn docs_shot_path(step_tag: &str, caption: &str) -> std::path::PathBuf {
+    let dir = std::env::var("DOCS_DIR").unwrap_or_else(|_| "docs/shots".to_string());
+    let mut slug = String::new();
+    for c in caption.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_matches('-').chars().take(40).collect();
+    std::path::Path::new(&dir).join(format!("step-{step_tag}-{slug}.png"))
+}
+
+/// When OTP_INPUT_SELECTOR is set, wait for that input (OTP_INPUT_TIMEOUT_SECS, default
+/// 15) and click it via WebDriver so the xdotool digits land in the field.
+async fn focus_otp_input(driver: &WebDriver) -> Result<()> {
+    let Ok(selector) = std::env::var("OTP_INPUT_SELECTOR") else {
+        return Ok(());
+    };
+    let timeout = std::env::var("OTP_INPUT_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(15);
+    wait_for_element(driver, &selector, Duration::from_secs(timeout), Duration::from_millis(250))
+        .await
+        .with_context(|| format!("OTP input '{selector}' never appeared"))?;
+    driver.find(By::Css(&selector)).await?.click().await?;
+    log_info!("🔐 Focused OTP input '{selector}'");
+    Ok(())
+}
+
+/// Map a point in screenshot pixels to X11 screen coordinates via Chrome's window
+/// geometry, clamped to the display.
+fn screenshot_point_to_screen(
+    display: &str,
+    (sw, sh): (u32, u32),
+    dpr: f64,
+    pt: ViewportPoint,
+) -> Result<(i32, i32)> {
+    let (wx, wy, ww, wh) = get_window_or_display_geometry(display)?;
+    let norm = NormalizationInputs {
+        screenshot_w: sw as i32,
+        screenshot_h: sh as i32,
+        window_x: wx,
+        window_y: wy,
+        window_w: ww,
+        window_h: wh,
+        device_pixel_ratio: dpr,
+    };
+
+    let (sx, sy) = viewport_to_screen(norm, pt.x, pt.y);
+    let (dw, dh) = get_display_geometry(display)?;
+    Ok((sx.clamp(0, dw - 1), sy.clamp(0, dh - 1)))
+}
+
+const CALIBRATION_PAGE: &str = r#"<!doctype html>
+<html><head><title>click calibration</title><style>
+  html, body { margin: 0; height: 100%; background: #fff; }
+  #target { position: fixed; left: 60%; top: 55%; width: 24px; height: 24px;
+            margin: -12px 0 0 -12px; background: #e00; border-radius: 50%; }
+</style></head><body>
+<div id="target"></div>
+<script>
+  window.__lastClick = null;
+  document.addEventListener("mousedown", e => { window.__lastClick = [e.clientX, e.clientY]; }, true);
+</script>
+</body></html>"#;
+
+/// --calibrate: open a generated page with a known target, click its centre through the
+/// normal screenshot → screen mapping + xdotool, read back where the browser saw the
+/// click, and print the CLICK_X/Y_OFFSET_PX that cancel the error. A second click with
+/// the recommended offsets reports the residual.
+async fn calibrate_click_offsets(bundle: &driver::DriverBundle) -> Result<()> {
+    if bundle.headless {
+        anyhow::bail!("--calibrate drives the real cursor; run headful (HEADFUL=1)");
+    }
+    ensure_xdotool()?;
+    let display = bundle.display.as_str();
+
+    let page = artifact_path("calibrate.html");
+    fs::write(&page, CALIBRATION_PAGE)?;
+    let page = fs::canonicalize(&page)?;
+    bundle.driver.goto(format!("file://{}", page.display())).await?;
+    sleep(Duration::from_millis(500)).await;
+    if let Err(e) = focus_chrome_window(display) {
+        log_warn!("⚠️ Could not focus Chrome window: {e:#}");
+    }
+
+    let target = bundle
+        .driver
+        .execute(
+            "const r = document.getElementById('target').getBoundingClientRect();\
+             return [r.left + r.width / 2, r.top + r.height / 2];",
+            Vec::new(),
+        )
+        .await?;
+    let target: (f64, f64) = serde_json::from_value(target.json().clone())
+        .context("calibration page did not report its target")?;
+    let dpr = device_pixel_ratio(&bundle.driver).await;
+
+    let read_offset = |key: &str| -> i32 {
+        std::env::var(key).ok().and_then(|s| s.parse().ok()).unwrap_or(0)
+    };
+    let (mut x_off, mut y_off) = (read_offset("CLICK_X_OFFSET_PX"), read_offset("CLICK_Y_OFFSET_PX"));
+
+    for pass in 1..=2 {
+        std::env::set_var("CLICK_X_OFFSET_PX", x_off.to_string());
+        std::env::set_var("CLICK_Y_OFFSET_PX", y_off.to_string());
+        bundle.driver.execute("window.__lastClick = null;", Vec::new()).await?;
+
+        let shot = artifact_path("calibrate.png");
+        let (path, bytes) = screenshot_bytes(&bundle.driver, &shot.to_string_lossy(), false).await?;
+        let size = png_dimensions(&bytes)?;
+        let _ = fs::remove_file(path);
+
+        let pt = ViewportPoint {
+            x: (target.0 * dpr).round() as i32,
+            y: (target.1 * dpr).round() as i32,
+            double: false,
+            confidence: None,
+        };
+        let (sx, sy) = screenshot_point_to_screen(display, size, dpr, pt)?;
+        xdotool_move_and_click(display, sx, sy, false)?;
+        sleep(Duration::from_millis(300)).await;
+
+        let landed = bundle.driver.execute("return window.__lastClick;", Vec::new()).await?;
+        let landed: (f64, f64) = serde_json::from_value(landed.json().clone()).map_err(|_| {
+            anyhow::anyhow!(
+                "pass {pass}: the click at screen ({sx}, {sy}) never reached the page; \
+                 check that Chrome is focused and the offsets are roughly right"
+            )
+        })?;
+
+        // CSS px error → screen px (one CSS px spans `dpr` device pixels)
+        let dx = ((target.0 - landed.0) * dpr).round() as i32;
+        let dy = ((target.1 - landed.1) * dpr).round() as i32;
+        log_info!(
+            "🎯 pass {pass}: offsets ({x_off}, {y_off}) → clicked screen ({sx}, {sy}), landed css ({:.0}, {:.0}), target ({:.0}, {:.0}), error ({dx}, {dy}) px",
+            landed.0, landed.1, target.0, target.1
+        );
+        if pass == 1 {
+            x_off += dx;
+            y_off += dy;
+        }
+    }
+
+    println!("Recommended:\n  CLICK_X_OFFSET_PX={x_off}\n  CLICK_Y_OFFSET_PX={y_off}");
+    Ok(())
+}
+
+/// Region of interest around a previous click for the retry pass's denser grid:
+/// a box of 3× GRID_STEP on each side of the point (clamped to the image by the overlay).
+fn focus_around(pt: ViewportPoint) -> (u32, u32, u32, u32) {
+    let step: u32 = std::env::var("GRID_STEP").ok().and_then(|s| s.parse().ok()).unwrap_or(50);
+    let half = step.saturating_mul(3);
+    let x = (pt.x.max(0) as u32).saturating_sub(half);
+    let y = (pt.y.max(0) as u32).saturating_sub(half);
+    (x, y, half * 2, half * 2)
+}
+
+/// Screenshot the current page and ask a yes/no question about it.
+/// The screenshot is deleted unless KEEP_OBSERVER_SCREENSHOTS=1.
+async fn ask_about_page(
+    bundle: &driver::DriverBundle,
+    cfg: &OpenAIConfig,
+    question: &str,
+) -> Result<ValidationRecord> {
+    // Questions may be about content below the fold; clicks never use this capture
+    let full_page = std::env::var("VALIDATION_FULL_PAGE").map_or(false, |v| v == "1");
+    let shot = artifact_path("validation.png");
+    let (path, bytes) = screenshot_bytes(&bundle.driver, &shot.to_string_lossy(), full_page).await?;
+    let verdict = ask_boolean_question(cfg, &bytes, question).await?;
+    if std::env::var("KEEP_OBSERVER_SCREENSHOTS").map_or(true, |v| v != "1") {
+        let _ = fs::remove_file(path);
+    }
+    Ok(ValidationRecord {
+        question: question.to_string(),
+        answer: verdict.answer,
+        confidence: verdict.confidence,
+        reasoning: verdict.reasoning,
+    })
+}
+
+/// HUMAN_IN_LOOP=1: before a screenshot click, ask whether the page is a CAPTCHA or other
+/// verification wall and, while it is, pause for a person to clear it.
+async fn wait_out_challenge(bundle: &driver::DriverBundle, cfg: &OpenAIConfig) -> Result<()> {
+    if std::env::var("HUMAN_IN_LOOP").map_or(true, |v| v != "1") {
+        return Ok(());
+    }
+    const QUESTION: &str = "Is this page showing a CAPTCHA, bot check, or other verification \
+                            challenge that a human must solve before continuing?";
+    while ask_about_page(bundle, cfg, QUESTION).await?.answer {
+        wait_for_human("Verification challenge detected; the click is on hold.").await;
+    }
+    Ok(())
+}
+
+/// Pause a human-driven run until the operator has dealt with `reason` in the browser.
+async fn wait_for_human(reason: &str) {
+    wait_for_enter_or_resume(&format!("🧑 {reason} Solve it in the browser, then")).await;
+}
+
+/// After each client: with PAUSE_BETWEEN_CLIENTS=1 on a terminal, wait for Enter (or
+/// RUN_DIR/RESUME); otherwise sleep CLIENT_DELAY_SECS if set.
+async fn pause_between_clients() {
+    use std::io::IsTerminal;
+
+    let pause = std::env::var("PAUSE_BETWEEN_CLIENTS").map_or(false, |v| v == "1");
+    if pause && std::io::stdin().is_terminal() {
+        wait_for_enter_or_resume("⏸️ Client finished; inspect the portal, then").await;
+        return;
+    }
+    let delay: u64 = std::env::var("CLIENT_DELAY_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    if delay > 0 {
+        log_info!("⏸️ Waiting {delay}s before the next client (CLIENT_DELAY_SECS)");
+        sleep(Duration::from_secs(delay)).await;
+    }
+}
+
+/// Block until the operator presses Enter or creates RUN_DIR/RESUME; `prompt` is
+/// completed with "press Enter or `touch …` to resume".
+/// Stdin comes from the shared `console` reader, so resuming via the file leaves no
+/// pending read to swallow a later prompt's answer.
+async fn wait_for_enter_or_resume(prompt: &str) {
+    let resume = artifact_path("RESUME");
+    let _ = fs::remove_file(&resume);
+    console::discard_pending_lines().await;
+    log_warn!("{prompt} press Enter or `touch {}` to resume.", resume.display());
+
+    // Operator time: left out of the step watchdog
+    console::operator_pause(async {
+        let mut stdin_open = true;
+        loop {
+            tokio::select! {
+                line = console::next_line(), if stdin_open => {
+                    // EOF (no terminal) closes stdin; only a real line counts as "resume"
+                    if line.is_some() {
+                        break;
+                    }
+                    stdin_open = false;
+                }
+                _ = sleep(Duration::from_millis(500)) => {
+                    if resume.exists() {
+                        let _ = fs::remove_file(&resume);
+                        break;
+                    }
+                }
+            }
+        }
+    })
+    .await;
+    log_info!("▶️ Resuming");
+}
+
+/// Audit-trail capture to RUN_DIR/step-NN-<phase>.png. Failures are logged, not fatal.
+async fn save_step_screenshot(bundle: &driver::DriverBundle, step_no: usize, phase: &str) {
+    let path = artifact_path(&format!("step-{}-{phase}.png", steplog::step_tag()));
+    if let Err(e) = screenshot_bytes(&bundle.driver, &path.to_string_lossy(), false).await {
+        log_warn!("(non-fatal) failed to save {phase} screenshot for step {step_no}: {e}");
+    }
+}
+
+/// Execute a single automation step, with optional before/after audit screenshots
+/// (SAVE_STEP_SCREENSHOTS=1). These are kept regardless of KEEP_OBSERVER_SCREENSHOTS.
+/// Emits one structured step record (LOG_FORMAT=text|json) and returns its summary.
 async fn execute_step(
+    step_no: usize,
     step: &Step,
     bundle: &mut driver::DriverBundle,
     display: &str,
     openai_cfg: &Option<OpenAIConfig>,
     sheets: &SheetsClient,
-) -> Result<()> {
+) -> Result<StepResult> {
+    let save_shots = std::env::var("SAVE_STEP_SCREENSHOTS").map_or(false, |v| v == "1");
+
+    if save_shots {
+        save_step_screenshot(bundle, step_no, "before").await;
+    }
+
+    // Keep in-memory before/after frames for click steps to spot no-op clicks.
+    let skipped = bundle.headless && needs_xdotool(step);
+    let diff_before = if is_click_step(step) && !skipped && !mouse::dry_run_enabled() {
+        bundle.driver.screenshot_as_png().await.ok()
+    } else {
+        None
+    };
+
+    let started = std::time::Instant::now();
+    let result = run_step(step, bundle, display, openai_cfg, sheets).await;
+    let duration_ms = started.elapsed().as_millis();
+
+    if let (Some(before), true) = (diff_before, result.is_ok()) {
+        sleep(Duration::from_millis(250)).await; // let the UI react before comparing
+        if let Ok(after) = bundle.driver.screenshot_as_png().await {
+            let ratio = tokio::task::spawn_blocking(move || page_changed(&before, &after))
+                .await
+                .unwrap_or(1.0);
+            if ratio < 0.001 {
+                log_warn!(
+                    "⚠️ step {step_no} {}: click had no visible effect (Δ {:.3}%)",
+                    step_kind(step),
+                    ratio * 100.0
+                );
+            } else {
+                log_info!("🔍 step {step_no} {}: page changed {:.2}%", step_kind(step), ratio * 100.0);
+            }
+        }
+    }
+
+    if save_shots {
+        save_step_screenshot(bundle, step_no, "after").await;
+    }
+
+    let (outcome, error, details) = match &result {
+        Ok(details) => ("ok", None, Some(details)),
+        Err(e) => match e.downcast_ref::<ControlFlowError>() {
+            Some(ControlFlowError::StopClient) => ("stop_client", None, None),
+            Some(ControlFlowError::AbortProgram) => ("abort", None, None),
+            _ => ("error", Some(format!("{e:#}")), None),
+        },
+    };
+    steplog::log_step(&StepRecord {
+        step_no,
+        step_type: step_kind(step),
+        outcome,
+        duration_ms,
+        error,
+        validation: details.and_then(|d| d.validation.as_ref()),
+    });
+
+    result.map(|details| StepResult {
+        validation: details.validation.as_ref().map(|v| v.answer),
+        used_fallback: details.used_fallback,
+        nested: details.nested,
+    })
+}
+
+/// Stable variant name for logs and reports.
+fn step_kind(step: &Step) -> &'static str {
+    match step {
+        Step::BeginClient { .. } => "BeginClient",
+        Step::VisitUrl { .. } => "VisitUrl",
+        Step::GoBack => "GoBack",
+        Step::Forward => "Forward",
+        Step::Reload => "Reload",
+        Step::TypeText { .. } => "TypeText",
+        Step::TypeInto { .. } => "TypeInto",
+        Step::Focus { .. } => "Focus",
+        Step::FillForm { .. } => "FillForm",
+        Step::TypeKey { .. } => "TypeKey",
+        Step::TypeOTP { .. } => "TypeOTP",
+        Step::ResetZoom => "ResetZoom",
+        Step::Wait(_) => "Wait",
+        Step::WaitForElement { .. } => "WaitForElement",
+        Step::WaitForHidden { .. } => "WaitForHidden",
+        Step::SubmitForm { .. } => "SubmitForm",
+        Step::ClickStage { .. } => "ClickStage",
+        Step::ClickCheckbox { .. } => "ClickCheckbox",
+        Step::ClickOptionsMenu { .. } => "ClickOptionsMenu",
+        Step::ClickTemplate { .. } => "ClickTemplate",
+        Step::ClickCreate { .. } => "ClickCreate",
+        Step::ClickInvoiceAmount { .. } => "ClickInvoiceAmount",
+        Step::SelectDropdown { .. } => "SelectDropdown",
+        Step::ClickByDom { .. } => "ClickByDom",
+        Step::ClickByText { .. } => "ClickByText",
+        Step::ClickInRow { .. } => "ClickInRow",
+        Step::ClickByLlm { .. } => "ClickByLlm",
+        Step::Hover { .. } => "Hover",
+        Step::DragAndDrop { .. } => "DragAndDrop",
+        Step::Assert { .. } => "Assert",
+        Step::AssertText { .. } => "AssertText",
+        Step::WaitForUrlChange { .. } => "WaitForUrlChange",
+        Step::WaitForNetworkIdle { .. } => "WaitForNetworkIdle",
+        Step::SetWindowSize { .. } => "SetWindowSize",
+        Step::EnsureAbsent { .. } => "EnsureAbsent",
+        Step::Branch { .. } => "Branch",
+        Step::Screenshot { .. } => "Screenshot",
+        Step::AnnotateShot { .. } => "AnnotateShot",
+        Step::UpdateSheetCell { .. } => "UpdateSheetCell",
+        Step::VerifySheetCell { .. } => "VerifySheetCell",
+        Step::UpdateMeBasedOnColumns { .. } => "UpdateMeBasedOnColumns",
+        Step::StopClient => "StopClient",
+        Step::Abort => "Abort",
+    }
+}
+
+/// Steps whose effect we verify with a before/after screenshot diff.
+fn is_click_step(step: &Step) -> bool {
+    matches!(
+        step,
+        Step::SubmitForm { .. }
+            | Step::ClickStage { .. }
+            | Step::ClickCheckbox { .. }
+            | Step::ClickOptionsMenu { .. }
+            | Step::ClickTemplate { .. }
+            | Step::ClickCreate { .. }
+            | Step::ClickInvoiceAmount { .. }
+            | Step::ClickByDom { .. }
+            | Step::ClickByText { .. }
+            | Step::ClickInRow { .. }
+            | Step::ClickByLlm { .. }
+            | Step::DragAndDrop { .. }
+    )
+}
+
+/// Steps that drive the real cursor/keyboard through xdotool.
+fn needs_xdotool(step: &Step) -> bool {
+    match step {
+        Step::TypeText { .. }
+        | Step::TypeKey { .. }
+        | Step::TypeOTP { .. }
+        | Step::ResetZoom
+        | Step::DragAndDrop { .. } => true,
+        Step::ClickByLlm { .. } => !use_webdriver_clicks(),
+        _ => false,
+    }
+}
+
+async fn run_step(
+    step: &Step,
+    bundle: &mut driver::DriverBundle,
+    display: &str,
+    openai_cfg: &Option<OpenAIConfig>,
+    sheets: &SheetsClient,
+) -> Result<StepDetails> {
+    let mut details = StepDetails::default();
+
+    if bundle.headless && needs_xdotool(step) {
+        log_info!("⏭️ Skipping {} in headless mode (needs xdotool)", step_kind(step));
+        return Ok(details);
+    }
+    if needs_xdotool(step) {
+        ensure_xdotool().with_context(|| {
+            let hint = if matches!(step, Step::ClickByLlm { .. }) {
+                " (or set CLICK_BACKEND=webdriver)"
+            } else {
+                ""
+            };
+            format!("{} drives the real mouse/keyboard and needs xdotool{hint}", step_kind(step))
+        })?;
+    }
+
     match step {
         Step::BeginClient { row } => {
-            println!("👤 Begin client block (sheet row={row})");
+            log_info!("👤 Begin client block (sheet row={row})");
         }
 
         Step::VisitUrl { url, .. } => {
-            println!("🌐 Visit: {url}");
+            log_info!("🌐 Visit: {url}");
             bundle.driver.goto(url).await?;
         }
 
+        Step::GoBack => {
+            log_info!("⬅️ Navigating back");
+            bundle.driver.back().await?;
+        }
+
+        Step::Forward => {
+            log_info!("➡️ Navigating forward");
+            bundle.driver.forward().await?;
+        }
+
+        Step::Reload => {
+            log_info!("🔄 Reloading page");
+            bundle.driver.refresh().await?;
+        }
+
         Step::TypeText { text, per_char_delay_ms, .. } => {
-            ensure_xdotool()?;
-            type_text(display, text, *per_char_delay_ms)?;
+            type_text(display, text, *per_char_delay_ms).await?;
+        }
+
+        Step::Focus { selector } => {
+            // Pin keyboard focus on one element so the next xdotool TypeText/TypeOTP lands there
+            let el = bundle
+                .driver
+                .find(By::Css(selector.as_str()))
+                .await
+                .with_context(|| format!("Focus: no element matches '{selector}'"))?;
+            let focused = bundle
+                .driver
+                .execute(
+                    "arguments[0].scrollIntoView({block:'center'}); arguments[0].focus();\
+                     return document.activeElement === arguments[0];",
+                    vec![el.to_json()?],
+                )
+                .await?
+                .json()
+                .as_bool()
+                .unwrap_or(false);
+            if !focused {
+                // Some widgets only take focus from a real click (e.g. custom inputs)
+                el.click().await.with_context(|| format!("Focus: '{selector}' rejected focus() and click"))?;
+            }
+            // xdotool types into the focused X window, so Chrome must be on top too
+            if !bundle.headless {
+                if let Err(e) = focus_chrome_window(display) {
+                    log_warn!("⚠️ Could not focus Chrome window: {e:#}");
+                }
+            }
+            log_info!("🎯 Focused '{selector}'{}", if focused { "" } else { " (by click)" });
+        }
+
+        Step::TypeInto { selector, text, clear_first } => {
+            // WebDriver input into a specific field; independent of OS window focus
+            let el = bundle
+                .driver
+                .find(By::Css(selector.as_str()))
+                .await
+                .with_context(|| format!("TypeInto: no element matches '{selector}'"))?;
+            if mouse::dry_run_enabled() {
+                log_info!("[DRY_RUN] would type {} chars into '{selector}'", text.chars().count());
+            } else {
+                if *clear_first {
+                    el.clear().await?;
+                }
+                el.send_keys(text.as_str()).await?;
+                log_info!("⌨️ Typed {} chars into '{selector}'", text.chars().count());
+            }
+        }
+
+        Step::FillForm { fields, delay_ms } => {
+            // Declarative TypeInto over several fields; stops at the first field that fails
+            let delay = delay_ms.unwrap_or_else(|| {
+                std::env::var("FILL_FORM_DELAY_MS").ok().and_then(|s| s.parse().ok()).unwrap_or(150)
+            });
+            for (i, (selector, value)) in fields.iter().enumerate() {
+                let field_no = i + 1;
+                let el = bundle
+                    .driver
+                    .find(By::Css(selector.as_str()))
+                    .await
+                    .with_context(|| format!("FillForm field {field_no}: no element matches '{selector}'"))?;
+                if mouse::dry_run_enabled() {
+                    log_info!("[DRY_RUN] would fill '{selector}' with {} chars", value.chars().count());
+                } else {
+                    el.clear()
+                        .await
+                        .with_context(|| format!("FillForm field {field_no} ('{selector}'): clear failed"))?;
+                    el.send_keys(value.as_str())
+                        .await
+                        .with_context(|| format!("FillForm field {field_no} ('{selector}'): typing failed"))?;
+                }
+                if field_no < fields.len() {
+                    sleep(Duration::from_millis(delay)).await;
+                }
+            }
+            log_info!("⌨️ Filled {} form field(s)", fields.len());
         }
 
         Step::TypeKey { key, .. } => {
@@ -82,12 +868,33 @@ async fn execute_step(
         }
 
         Step::TypeOTP { uid, .. } => {
-            let (_, _, code) = fetch_keeper_creds_sync().unwrap_or_default();
-            if let Some(otp) = code {
-                ensure_xdotool()?;
-                type_text(display, &otp, 150)?;
-                println!("🔐 Typed OTP for UID {uid}");
+            // The step names its own record; a login can't go on without the code
+            let provider = otp::OtpSource::from_env()?;
+            let otp = provider
+                .fetch_otp(uid)
+                .await
+                .with_context(|| format!("OTP fetch failed for UID {uid}"))?
+                .with_context(|| format!("no OTP available for UID {uid}"))?;
+            focus_otp_input(&bundle.driver).await?;
+            if std::env::var("OTP_SPLIT_BOXES").map_or(false, |v| v == "1") {
+                // One box per digit: type each digit, Tab to the next box
+                let digits: Vec<char> = otp.chars().collect();
+                for (i, d) in digits.iter().enumerate() {
+                    type_text(display, &d.to_string(), 0).await?;
+                    if i + 1 < digits.len() {
+                        keyboard::xdotool_key(display, "Tab")?;
+                        sleep(Duration::from_millis(150)).await;
+                    }
+                }
+            } else {
+                type_text(display, &otp, 150).await?;
             }
+            log_info!("🔐 Typed OTP for UID {uid}");
+        }
+
+        Step::SetWindowSize { width, height } => {
+            let (vw, vh) = set_window_size(&bundle.driver, *width, *height).await?;
+            log_info!("📐 window resized to {width}x{height}; viewport now {vw}x{vh}");
         }
 
         Step::ResetZoom => {
@@ -98,6 +905,28 @@ async fn execute_step(
             sleep(Duration::from_secs(*secs)).await;
         }
 
+        Step::WaitForElement { selector, timeout_secs, poll_ms } => {
+            let waited = wait_for_element(
+                &bundle.driver,
+                selector,
+                Duration::from_secs(*timeout_secs),
+                Duration::from_millis(*poll_ms),
+            )
+            .await?;
+            log_info!("⏱️ '{selector}' appeared after {}ms", waited.as_millis());
+        }
+
+        Step::WaitForHidden { selector, timeout_secs } => {
+            let waited = wait_for_hidden(
+                &bundle.driver,
+                selector,
+                Duration::from_secs(*timeout_secs),
+                Duration::from_millis(250),
+            )
+            .await?;
+            log_info!("⏱️ '{selector}' gone after {}ms", waited.as_millis());
+        }
+
         Step::SubmitForm { .. } => {
             bundle.driver
                 .find(By::Css("button[type='submit']"))
@@ -130,60 +959,321 @@ async fn execute_step(
             click_invoice_amount_input(&bundle.driver).await?;
         }
 
-        Step::ClickByDom { prompt, double, .. } => {
-            let cfg = openai_cfg.as_ref().context("OpenAI not configured")?;
-            click_by_llm_dom_first(&bundle.driver, cfg, prompt, *double).await?;
+        Step::SelectDropdown { selector, option_text } => {
+            select_dropdown_option(&bundle.driver, selector, option_text).await?;
         }
 
-        Step::ClickByLlm { prompt, double, .. } => {
+        Step::ClickByDom { prompt, double, nth, .. } => {
+            let prompt = client::render_prompt(prompt)?;
+            // Without OpenAI config this runs heuristic-only (as with DOM_HEURISTIC_ONLY=1)
+            let outcome =
+                click_by_llm_dom_first(&bundle.driver, openai_cfg.as_ref(), &prompt, *double, *nth).await?;
+
+            let min_conf: f32 = std::env::var("DOM_MIN_CONFIDENCE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0.4);
+            details.used_fallback = outcome.used_fallback;
+            if outcome.used_fallback {
+                log_warn!("⚠️ ClickByDom used the heuristic fallback (candidate #{})", outcome.chosen_idx);
+            } else if let Some(c) = outcome.confidence.filter(|c| *c < min_conf) {
+                log_warn!(
+                    "⚠️ ClickByDom low confidence {c:.2} < {min_conf:.2} (candidate #{})",
+                    outcome.chosen_idx
+                );
+            }
+        }
+
+        Step::ClickByText { text, r#match, double, nth } => {
+            click_by_text(&bundle.driver, text, *r#match, *double, *nth).await?;
+        }
+
+        Step::ClickInRow { row_text, control_prompt, double } => {
+            let row_text = client::render_prompt(row_text)?;
+            let control_prompt = client::render_prompt(control_prompt)?;
+            let outcome = click_in_row(
+                &bundle.driver,
+                openai_cfg.as_ref(),
+                &row_text,
+                &control_prompt,
+                *double,
+            )
+            .await?;
+            details.used_fallback = outcome.used_fallback;
+            if outcome.used_fallback {
+                log_warn!("⚠️ ClickInRow used the heuristic fallback in row {row_text:?}");
+            }
+        }
+
+        Step::Hover { prompt, dwell_ms } => {
+            let outcome = hover_by_llm_dom_first(&bundle.driver, openai_cfg.as_ref(), prompt).await?;
+            details.used_fallback = outcome.used_fallback;
+            if outcome.used_fallback {
+                log_warn!("⚠️ Hover used the heuristic fallback (candidate #{})", outcome.chosen_idx);
+            }
+
+            // Give hover menus time to open before the next step looks for their items
+            let dwell = dwell_ms.unwrap_or_else(|| {
+                std::env::var("HOVER_DWELL_MS").ok().and_then(|s| s.parse().ok()).unwrap_or(400)
+            });
+            sleep(Duration::from_millis(dwell)).await;
+        }
+
+        Step::ClickByLlm { prompt, double, validation_question, max_click_retries, .. } => {
             let cfg = openai_cfg.as_ref().context("OpenAI not configured")?;
+            let prompt = &client::render_prompt(prompt)?;
 
-            let (path, bytes) = screenshot_bytes(&bundle.driver, "screenshot.png").await?;
-            let (sw, sh) = png_dimensions(&bytes)?;
-            let (wx, wy, ww, wh) = get_active_window_geometry(display)?;
+            // A verification wall would get a confident click on a random point
+            wait_out_challenge(bundle, cfg).await?;
 
-            let mut pt: ViewportPoint =
-                call_openai_for_point(cfg, &bytes, prompt).await?;
-            if let Some(force) = *double {
-                pt.double = force;
+            // Other windows on the VNC desktop can steal focus; geometry must be Chrome's.
+            // Display captures skip this so an OS dialog stays on top.
+            if !use_webdriver_clicks() && !display_screenshot_source() {
+                if let Err(e) = focus_chrome_window(display) {
+                    log_warn!("⚠️ Could not focus Chrome window: {e:#}");
+                }
             }
 
-            let norm = NormalizationInputs {
-                screenshot_w: sw as i32,
-                screenshot_h: sh as i32,
-                window_x: wx,
-                window_y: wy,
-                window_w: ww,
-                window_h: wh,
-            };
+            let mut last_pt = click_by_llm_screenshot(bundle, display, cfg, prompt, *double, None).await?;
+
+            if let Some(question) = validation_question {
+                let mut retries = 0;
+                loop {
+                    // Give the page a moment to react before judging the click
+                    sleep(Duration::from_millis(800)).await;
+
+                    let verdict = ask_about_page(bundle, cfg, question).await?;
+                    details.validation = Some(verdict.clone());
+
+                    if verdict.answer {
+                        break;
+                    }
+                    // The point missed: don't let LLM_CACHE replay it
+                    evict_last_cached_point();
+                    if retries >= *max_click_retries {
+                        log_warn!("❌ Validation still failing after {retries} click retries");
+                        break;
+                    }
 
-            let (sx, sy) = viewport_to_screen(norm, pt.x, pt.y);
-            let (dw, dh) = get_display_geometry(display)?;
+                    retries += 1;
+                    log_info!("🔁 Validation failed, retrying click ({retries}/{max_click_retries})");
+                    let retry_prompt = format!(
+                        "{prompt}\n\nNote: the previous attempt missed the target \
+                         (check \"{question}\" came back false). Choose a different point."
+                    );
+                    let focus = Some(focus_around(last_pt));
+                    last_pt = click_by_llm_screenshot(bundle, display, cfg, &retry_prompt, *double, focus).await?;
+                }
+            }
+        }
+
+        Step::DragAndDrop { from_prompt, to_prompt } => {
+            let cfg = openai_cfg.as_ref().context("OpenAI not configured")?;
+            if !display_screenshot_source() {
+                if let Err(e) = focus_chrome_window(display) {
+                    log_warn!("⚠️ Could not focus Chrome window: {e:#}");
+                }
+            }
+
+            ensure_default_zoom(bundle, display, true).await?;
 
-            xdotool_move_and_click(
-                display,
-                sx.clamp(0, dw - 1),
-                sy.clamp(0, dh - 1),
-                pt.double,
-            )?;
+            // Both endpoints come from the same screenshot so they share one layout
+            let shot = artifact_path("screenshot.png");
+            let (path, bytes) = vision_screenshot(bundle, display, &shot.to_string_lossy()).await?;
+            let size = png_dimensions(&bytes)?;
+            let page_url = bundle.driver.current_url().await?.to_string();
+            let dpr = device_pixel_ratio(&bundle.driver).await;
 
+            let from_pt = call_openai_for_point_cached(cfg, &bytes, from_prompt, &page_url, None).await?;
+            let to_pt = call_openai_for_point_cached(cfg, &bytes, to_prompt, &page_url, None).await?;
             let _ = fs::remove_file(path);
+
+            let from = vision_point_to_screen(display, size, dpr, from_pt)?;
+            let to = vision_point_to_screen(display, size, dpr, to_pt)?;
+            xdotool_drag(display, from, to).await?;
+            log_info!("🖱️ [xdotool] dragged screen {from:?} → {to:?}");
+        }
+
+        Step::AssertText { substring, present, timeout_secs, selector } => {
+            // Deterministic text check; no screenshot or model call
+            let waited = wait_for_text(
+                &bundle.driver,
+                substring,
+                *present,
+                selector.as_deref(),
+                Duration::from_secs(*timeout_secs),
+                Duration::from_millis(250),
+            )
+            .await?;
+            let state = if *present { "present" } else { "absent" };
+            log_info!("✅ text {substring:?} {state} after {}ms", waited.as_millis());
+        }
+
+        Step::WaitForUrlChange { from_contains, to_contains, timeout_secs } => {
+            let (url, waited) = wait_for_url_change(
+                &bundle.driver,
+                from_contains.as_deref(),
+                to_contains.as_deref(),
+                Duration::from_secs(*timeout_secs),
+                Duration::from_millis(250),
+            )
+            .await?;
+            log_info!("🧭 navigated to {url} after {}ms", waited.as_millis());
         }
 
-        Step::UpdateSheetCell { row, col, value, success, yellow } => {
-            let color = if *yellow {
-                (255, 255, 0)
-            } else if *success {
-                (0, 255, 0)
+        Step::WaitForNetworkIdle { idle_ms, timeout_secs } => {
+            let waited = wait_for_network_idle(
+                &bundle.driver,
+                Duration::from_millis(*idle_ms),
+                Duration::from_secs(*timeout_secs),
+                Duration::from_millis(100),
+            )
+            .await?;
+            log_info!("🌐 network idle for {idle_ms}ms after {}ms", waited.as_millis());
+        }
+
+        Step::EnsureAbsent { text, selector, row, col } => {
+            // Re-run guard: if the thing we're about to create already exists, mark the
+            // sheet and skip the rest of this client instead of creating a duplicate
+            if page_contains(&bundle.driver, text, selector.as_deref()).await {
+                log_warn!("⚠️ {text:?} is already on the page; skipping the rest of this client");
+                if let (Some(row), Some(col)) = (row, col) {
+                    sheets
+                        .update_cell_value_and_color(*row, *col, "already exists", CellStatus::Yellow.rgb())
+                        .await?;
+                }
+                return Err(ControlFlowError::StopClient.into());
+            }
+            log_info!("✅ {text:?} not present; continuing");
+        }
+
+        Step::Assert { question, on_fail } => {
+            let cfg = openai_cfg.as_ref().context("OpenAI not configured")?;
+
+            let max_retries: usize = if on_fail.eq_ignore_ascii_case("retry") {
+                std::env::var("ASSERT_MAX_RETRIES").ok().and_then(|s| s.parse().ok()).unwrap_or(3)
             } else {
-                (255, 0, 0)
+                0
             };
 
+            let mut verdict = ask_about_page(bundle, cfg, question).await?;
+            let mut tries = 0;
+            while !verdict.answer && tries < max_retries {
+                tries += 1;
+                log_info!("🔁 Assertion false, re-checking ({tries}/{max_retries}): {question}");
+                sleep(Duration::from_secs(2)).await;
+                verdict = ask_about_page(bundle, cfg, question).await?;
+            }
+
+            let passed = verdict.answer;
+            let reasoning = verdict.reasoning.clone().unwrap_or_default();
+            details.validation = Some(verdict);
+
+            if !passed {
+                match on_fail.to_ascii_lowercase().as_str() {
+                    "continue" => {
+                        log_warn!("⚠️ Assertion failed, continuing: {question} ({reasoning})");
+                        steplog::set_last_failure(Some(format!("assertion failed: {question} ({reasoning})")));
+                    }
+                    "abort" | "retry" => {
+                        anyhow::bail!(
+                            "Assertion failed (on_fail={on_fail}): {question:?} — {reasoning}"
+                        );
+                    }
+                    other => anyhow::bail!("Unknown Assert on_fail value '{other}' (use continue|abort|retry)"),
+                }
+            }
+        }
+
+        Step::Branch { question, then_steps, else_steps } => {
+            let cfg = openai_cfg.as_ref().context("OpenAI not configured")?;
+            let verdict = ask_about_page(bundle, cfg, question).await?;
+            let answer = verdict.answer;
+            if !answer {
+                let why = verdict.reasoning.clone().unwrap_or_default();
+                steplog::set_last_failure(Some(format!("{question} → no ({why})")));
+            }
+            details.validation = Some(verdict);
+
+            let (label, branch) = if answer { ("then", then_steps) } else { ("else", else_steps) };
+            log_info!("🔀 Branch {question:?} → {answer}: running {} {label} step(s)", branch.len());
+
+            // Nested steps share the parent's step number in logs and reach the run report
+            // through the parent's result; control flow (StopClient/Abort) and errors
+            // propagate out of the branch unchanged.
+            // Artifacts are tagged NN-bK so they don't overwrite the parent's.
+            let step_no: usize = std::env::var("CURRENT_STEP_NO").ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+            let parent_tag = steplog::step_tag();
+            for (k, nested) in branch.iter().enumerate() {
+                std::env::set_var("CURRENT_STEP_TAG", format!("{parent_tag}-b{}", k + 1));
+                let r = Box::pin(execute_step(step_no, nested, bundle, display, openai_cfg, sheets)).await;
+                // The parent's "after" screenshot uses its own tag again
+                std::env::set_var("CURRENT_STEP_TAG", &parent_tag);
+                details.nested.push(r?);
+            }
+        }
+
+        Step::Screenshot { name } => {
+            // Evidence capture: kept regardless of KEEP_OBSERVER_SCREENSHOTS
+            let file = name.replace(['/', '\\'], "_");
+            let path = artifact_path(&format!("{file}.png"));
+            screenshot_bytes(&bundle.driver, &path.to_string_lossy(), false).await?;
+        }
+
+        Step::AnnotateShot { prompt, caption } => {
+            let cfg = openai_cfg.as_ref().context("OpenAI not configured")?;
+            let prompt = &client::render_prompt(prompt)?;
+            let caption = client::render_prompt(caption)?;
+
+            let shot = artifact_path("screenshot.png");
+            let (path, bytes) = screenshot_bytes(&bundle.driver, &shot.to_string_lossy(), false).await?;
+            let page_url = bundle.driver.current_url().await?.to_string();
+            let dpr = device_pixel_ratio(&bundle.driver).await;
+            let pt = call_openai_for_point_cached(cfg, &bytes, prompt, &page_url, None).await?;
+            let _ = fs::remove_file(path);
+
+            // Box the control under the point; a bare point gets a fixed box around it
+            let target = match control_at_point(&bundle.driver, pt, dpr).await.and_then(|c| c.rect) {
+                Some(rect) => rect,
+                None => {
+                    let r = (24.0 * dpr).round() as u32;
+                    let (x, y) = (pt.x.max(0) as u32, pt.y.max(0) as u32);
+                    (x.saturating_sub(r), y.saturating_sub(r), 2 * r, 2 * r)
+                }
+            };
+            let annotated = overlay::annotate_target(&bytes, target, &caption)?;
+
+            let out = docs_shot_path(&steplog::step_tag(), &caption);
+            if let Some(dir) = out.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&out, annotated).with_context(|| format!("write {}", out.display()))?;
+            log_info!("🖼️ Annotated {prompt:?} at {target:?} → {}", out.display());
+        }
+
+        Step::UpdateSheetCell { row, col, value, status, note } => {
+            log_info!("📝 Sheet row={row} col={col} value={value:?} status={status}");
+            // `{reason}` in the note becomes the client's latest failure reason
+            let note = note.as_ref().map(|n| {
+                let reason = steplog::last_failure().unwrap_or_else(|| "no failure recorded".to_string());
+                n.replace("{reason}", &reason)
+            });
             sheets
-                .update_cell_value_and_color(*row, *col, value, color)
+                .update_cell_with_note(*row, *col, value, status.rgb(), note.as_deref())
                 .await?;
         }
 
+        Step::VerifySheetCell { row, col, expected } => {
+            // Read-back after a write: a write to the wrong tab/sheetId "succeeds" silently
+            let actual = sheets.read_cell_value(*row, *col).await?;
+            if actual.trim() != expected.trim() {
+                anyhow::bail!(
+                    "sheet cell row={row} col={col} is {actual:?}, expected {expected:?} (did the write land?)"
+                );
+            }
+            log_info!("✅ Sheet row={row} col={col} reads back {expected:?}");
+        }
+
         Step::UpdateMeBasedOnColumns { row, me_col, me_value, check_columns } => {
             // Read each operation column and check if all are "Y"
             let mut all_passed = true;
@@ -196,14 +1286,10 @@ async fn execute_step(
             }
 
             // Update ME: green if all passed, red otherwise
-            let color = if all_passed {
-                (0, 255, 0)  // Green
-            } else {
-                (255, 0, 0)  // Red
-            };
+            let status = if all_passed { CellStatus::Green } else { CellStatus::Red };
 
             sheets
-                .update_cell_value_and_color(*row, *me_col, me_value, color)
+                .update_cell_value_and_color(*row, *me_col, me_value, status.rgb())
                 .await?;
         }
 
@@ -216,13 +1302,135 @@ async fn execute_step(
         }
     }
 
+    Ok(details)
+}
+
+/// Index of the first BeginClient at or after `from` (steps.len() if there is none).
+fn next_client_start(steps: &[Step], from: usize) -> usize {
+    steps[from.min(steps.len())..]
+        .iter()
+        .position(|s| matches!(s, Step::BeginClient { .. }))
+        .map_or(steps.len(), |i| from + i)
+}
+
+/// The login portion of a plan: every step before the first BeginClient.
+fn login_steps(steps: &[Step]) -> &[Step] {
+    let end = steps
+        .iter()
+        .position(|s| matches!(s, Step::BeginClient { .. }))
+        .unwrap_or(0);
+    &steps[..end]
+}
+
+/// Ctrl-C arrived while a step outside the main loop (re-login) was running.
+#[derive(Debug)]
+struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// STEP_TIMEOUT_SECS (default 120): no single step may hang the run.
+fn step_timeout() -> Duration {
+    Duration::from_secs(std::env::var("STEP_TIMEOUT_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(120))
+}
+
+/// Run a step under the watchdog: None once it has run for `limit`, not counting time
+/// spent waiting on the operator (CAPTCHA pauses, SAFE_MODE prompts).
+async fn with_step_watchdog<F: std::future::Future>(limit: Duration, step: F) -> Option<F::Output> {
+    let started = std::time::Instant::now();
+    let paused_before = console::operator_paused_total();
+    tokio::pin!(step);
+    loop {
+        let active = started.elapsed().saturating_sub(console::operator_paused_total() - paused_before);
+        if active >= limit {
+            return None;
+        }
+        tokio::select! {
+            out = &mut step => return Some(out),
+            // Re-checked on wake: an operator pause in between pushes the deadline out
+            _ = sleep(limit - active) => {}
+        }
+    }
+}
+
+/// Run the login subsequence again (e.g. after the session expired mid-batch), before
+/// step `before_step_no`. Like Branch steps, re-login steps share that step's number and
+/// tag their artifacts `NN-login-KK`, so they don't overwrite the first login's. Each
+/// runs under the step watchdog; Ctrl-C stops the step with an `Interrupted` error.
+async fn run_login(
+    login: &[Step],
+    before_step_no: usize,
+    bundle: &mut driver::DriverBundle,
+    display: &str,
+    openai_cfg: &Option<OpenAIConfig>,
+    sheets: &SheetsClient,
+    interrupted: &mut (impl std::future::Future<Output = std::io::Result<()>> + Unpin),
+) -> Result<()> {
+    let step_timeout = step_timeout();
+    std::env::set_var("CURRENT_STEP_NO", before_step_no.to_string());
+    for (i, step) in login.iter().enumerate() {
+        let tag = format!("{before_step_no:02}-login-{:02}", i + 1);
+        std::env::set_var("CURRENT_STEP_TAG", &tag);
+        let timed = tokio::select! {
+            r = with_step_watchdog(
+                step_timeout,
+                execute_step(before_step_no, step, bundle, display, openai_cfg, sheets),
+            ) => r,
+            _ = &mut *interrupted => {
+                log_warn!("🛑 Interrupted during re-login step {tag} ({})", step_kind(step));
+                return Err(Interrupted.into());
+            }
+        };
+        timed
+            .with_context(|| format!("re-login step {tag} timed out after {}s (STEP_TIMEOUT_SECS)", step_timeout.as_secs()))?
+            .with_context(|| format!("re-login step {tag} ({}) failed", step_kind(step)))?;
+    }
     Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let result = run().await;
+    if result.is_err() {
+        // The WebDriver error alone rarely says why Chrome went away; chromedriver usually does
+        let tail = driver::chromedriver_log_tail(chromedriver_tail_lines());
+        if !tail.is_empty() {
+            eprintln!("--- last {} line(s) of chromedriver.log ---", tail.len());
+            for line in &tail {
+                eprintln!("{line}");
+            }
+            eprintln!("---");
+        }
+    }
+    result
+}
+
+/// CHROMEDRIVER_LOG_TAIL_LINES (default 40): how much of chromedriver.log a failure keeps.
+fn chromedriver_tail_lines() -> usize {
+    std::env::var("CHROMEDRIVER_LOG_TAIL_LINES").ok().and_then(|s| s.parse().ok()).unwrap_or(40)
+}
+
+async fn run() -> Result<()> {
+    let cli = Cli::parse();
     dotenvy::dotenv().ok();
-    ensure_xdotool()?;
+    profile::apply_profile(cli.profile.as_deref())?;
+    cli.apply_to_env();
+    if let Some(dir) = &cli.replay {
+        return openai_client::replay_dotmaps(dir);
+    }
+    // xdotool is checked per step (see run_step), so DOM/WebDriver-only plans run without it
+
+    if cli.calibrate {
+        let mut bundle = init_driver("about:blank").await?;
+        let result = calibrate_click_offsets(&bundle).await;
+        cleanup_driver(&mut bundle).await;
+        return result;
+    }
 
     let login_url = std::env::var("LOGIN_URL")
         .context("LOGIN_URL must be set")?;
@@ -239,32 +1447,163 @@ async fn main() -> Result<()> {
         .unwrap_or_else(|_| format!("{}!A1:Z1000", sheet_name));
     let values = sheets.fetch_sheet_values(&range).await?;
 
-    let plan = AutomationPlan::client_loop(&values)?;
-    let openai_cfg = OpenAIConfig::from_env().ok();
+    // PLAN_FILE (JSON/YAML list of steps) overrides the built-in per-client plan
+    let steps: Vec<Step> = match std::env::var("PLAN_FILE") {
+        Ok(path) if !path.trim().is_empty() => AutomationPlan::from_file(&path)?.steps,
+        _ => AutomationPlan::client_loop(&values)?.steps,
+    };
+    let openai_cfg = OpenAIConfig::from_env_optional()?;
+    if openai_cfg.is_none() {
+        log_warn!("⚠️ No OPENAI_API_KEY configured: LLM steps will fail, DOM clicks use heuristics only");
+    }
+    let comments = client::comments_by_row(&values);
+    // Rows as typed clients, for `{field}` placeholders in click prompts. Only clients
+    // that pass ONLY_CLIENTS/SKIP_CLIENTS and the comment markers are in the store, so a
+    // PLAN_FILE BeginClient block for any other row is skipped below.
+    let (clients_by_row, filter_rows): (std::collections::HashMap<usize, client::Client>, bool) =
+        match client::ClientStore::from_sheet_values(&values) {
+            Ok(store) => (store.clients.into_iter().map(|c| (c.row_index, c)).collect(), true),
+            Err(e) => {
+                log_warn!("⚠️ Could not map sheet rows to clients; prompt placeholders and client filters disabled: {e:#}");
+                (Default::default(), false)
+            }
+        };
+
+    // Watchdog: no single step may hang the run (e.g. a wedged WebDriver call)
+    let step_timeout = step_timeout();
+
+    // Ctrl-C: stop the current step, quit chromedriver and remove the temp profile
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+
+    let login = login_steps(&steps);
+
+    let run_started = std::time::Instant::now();
+    let mut report = RunReport::default();
 
     let mut step_idx = 0;
-    while step_idx < plan.steps.len() {
-        let step = &plan.steps[step_idx];
+    let mut client_started = false;
+    while step_idx < steps.len() {
+        let step = &steps[step_idx];
+        let step_no = step_idx + 1;
+
+        // A client left out by the filters: skip its whole BeginClient block
+        if let Step::BeginClient { row } = step {
+            if filter_rows && !clients_by_row.contains_key(row) {
+                log_info!("⏭️ Skipping row {row} (step {step_no}): client filtered out");
+                step_idx = next_client_start(&steps, step_idx + 1);
+                continue;
+            }
+        }
+
+        // Between clients: optional operator pause (or fixed delay) to inspect the portal
+        if matches!(step, Step::BeginClient { .. }) {
+            if client_started {
+                tokio::select! {
+                    _ = pause_between_clients() => {}
+                    _ = &mut interrupted => {
+                        log_warn!("🛑 Interrupted between clients; cleaning up");
+                        report.aborted = Some(format!("interrupted before step {step_no}"));
+                        exit_interrupted(&mut bundle, &mut report, run_started).await;
+                    }
+                }
+            }
+            client_started = true;
+        }
+
+        // Long batches can get logged out; re-run the login prefix before the next client
+        if matches!(step, Step::BeginClient { .. })
+            && step_idx > login.len()
+            && !login.is_empty()
+            && !is_logged_in(&bundle.driver).await
+        {
+            log_warn!("🔒 Session looks logged out before step {step_no}; re-running {} login steps", login.len());
+            if let Err(e) = run_login(login, step_no, &mut bundle, &display, &openai_cfg, &sheets, &mut interrupted).await {
+                if e.downcast_ref::<Interrupted>().is_some() {
+                    report.aborted = Some(format!("interrupted during re-login before step {step_no}"));
+                    exit_interrupted(&mut bundle, &mut report, run_started).await;
+                }
+                cleanup_driver(&mut bundle).await;
+                report.aborted = Some(format!("re-login before step {step_no} failed"));
+                finish_report(&mut report, run_started);
+                return Err(e);
+            }
+        }
+
+        std::env::set_var("CURRENT_STEP_NO", step_no.to_string());
+        std::env::set_var("CURRENT_STEP_TAG", format!("{step_no:02}"));
+        if let Step::BeginClient { row } = step {
+            let comment = comments.get(row).cloned();
+            if let Some(c) = &comment {
+                log_info!("💬 row {row} comment: {c}");
+            }
+            steplog::set_client_comment(comment);
+            steplog::set_last_failure(None);
+            client::set_current_client(clients_by_row.get(row).cloned());
+        }
+
+        let timed = tokio::select! {
+            r = with_step_watchdog(
+                step_timeout,
+                execute_step(step_no, step, &mut bundle, &display, &openai_cfg, &sheets),
+            ) => r,
+            // The in-flight step is dropped first, so the bundle is free to clean up here
+            _ = &mut interrupted => {
+                log_warn!("🛑 Interrupted during step {step_no} ({}); cleaning up", step_kind(step));
+                report.aborted = Some(format!("interrupted during step {step_no}"));
+                exit_interrupted(&mut bundle, &mut report, run_started).await;
+            }
+        };
+
+        let result = match timed {
+            Some(r) => r,
+            None => {
+                log_warn!(
+                    "⏰ Step {step_no} ({}) exceeded {}s; aborting run",
+                    step_kind(step),
+                    step_timeout.as_secs()
+                );
+                cleanup_driver(&mut bundle).await;
+                report.aborted = Some(format!("step {step_no} ({}) timed out", step_kind(step)));
+                finish_report(&mut report, run_started);
+                anyhow::bail!(
+                    "step {step_no} ({}) timed out after {}s (STEP_TIMEOUT_SECS)",
+                    step_kind(step),
+                    step_timeout.as_secs()
+                );
+            }
+        };
 
-        match execute_step(step, &mut bundle, &display, &openai_cfg, &sheets).await {
-            Ok(()) => {}
+        match result {
+            Ok(r) => {
+                if let Step::BeginClient { row } = step {
+                    report.begin_client(*row);
+                }
+                report.record(&r);
+            }
             Err(e) => {
                 if let Some(cf) = e.downcast_ref::<ControlFlowError>() {
                     match cf {
                         ControlFlowError::StopClient => {
-                            step_idx += 1;
-                            while step_idx < plan.steps.len() {
-                                if matches!(plan.steps[step_idx], Step::BeginClient { .. }) {
-                                    break;
-                                }
-                                step_idx += 1;
-                            }
+                            report.stop_client();
+                            step_idx = next_client_start(&steps, step_idx + 1);
                             continue;
                         }
-                        ControlFlowError::AbortProgram => return Err(e),
-                        ControlFlowError::Other(_) => return Err(e),
+                        ControlFlowError::AbortProgram | ControlFlowError::Other(_) => {
+                            report.aborted = Some(format!("step {step_no} ({}): {e}", step_kind(step)));
+                            finish_report(&mut report, run_started);
+                            return Err(e);
+                        }
                     }
                 } else {
+                    report.aborted = Some(format!("step {step_no} ({}) failed: {e}", step_kind(step)));
+                    finish_report(&mut report, run_started);
+                    if let Some(OpenAiError::BudgetExhausted { limit }) = e.downcast_ref::<OpenAiError>() {
+                        log_warn!(
+                            "💸 Step {step_no} ({}) hit the OpenAI budget of {limit} requests; aborting run",
+                            step_kind(step)
+                        );
+                    }
                     return Err(e);
                 }
             }
@@ -274,6 +1613,47 @@ async fn main() -> Result<()> {
     }
 
     cleanup_driver(&mut bundle).await;
-    println!("✅ Done.");
+    finish_report(&mut report, run_started);
+    log_info!("✅ Done.");
     Ok(())
+}
+
+/// Ctrl-C: quit chromedriver, remove the temp profile, write the report and exit 130.
+async fn exit_interrupted(
+    bundle: &mut driver::DriverBundle,
+    report: &mut RunReport,
+    started: std::time::Instant,
+) -> ! {
+    cleanup_driver(bundle).await;
+    if bundle.persist_profile {
+        log_info!("🍪 Kept persistent profile {}", bundle.user_data_dir.display());
+    } else if bundle.user_data_dir.exists() {
+        log_warn!("⚠️ Could not remove {}", bundle.user_data_dir.display());
+    } else {
+        log_info!("🧹 Removed {}", bundle.user_data_dir.display());
+    }
+    finish_report(report, started);
+    std::process::exit(130);
+}
+
+/// Fill in the run-wide totals, print the summary and, with SAVE_REPORT=1, write
+/// RUN_DIR/report.json. Write failures are logged, not fatal.
+fn finish_report(report: &mut RunReport, started: std::time::Instant) {
+    report.llm_requests = llm_requests_made();
+    report.wall_clock_ms = started.elapsed().as_millis();
+    if report.aborted.is_some() {
+        report.chromedriver_log_tail = driver::chromedriver_log_tail(chromedriver_tail_lines());
+    }
+    report.print();
+
+    if std::env::var("SAVE_REPORT").map_or(false, |v| v == "1") {
+        let path = artifact_path("report.json");
+        let written = serde_json::to_vec_pretty(report)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(std::fs::write(&path, bytes)?));
+        match written {
+            Ok(()) => log_info!("📊 Report written to {}", path.display()),
+            Err(e) => log_warn!("(non-fatal) failed to write {}: {e}", path.display()),
+        }
+    }
 }
\ No newline at end of file
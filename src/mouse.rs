@@ -8,6 +8,83 @@ pub fn ensure_xdotool() -> Result<()> {
     Ok(())
 }
  
+/// True when DRY_RUN=1: log clicks/keystrokes instead of sending them.
+pub fn dry_run_enabled() -> bool {
+    std::env::var("DRY_RUN").map_or(false, |v| v == "1")
+}
+ 
+/// True when HUMANIZE_CLICKS=1: glide the cursor in small steps and pause randomly before clicking.
+pub fn humanize_clicks_enabled() -> bool {
+    std::env::var("HUMANIZE_CLICKS").map_or(false, |v| v == "1")
+}
+ 
+/// Pseudo-random value in [lo, hi] for humanized timing. xorshift seeded from the
+/// clock; not for anything security-related (no RNG crate needed).
+pub fn jitter_between(lo: u64, hi: u64) -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static STATE: AtomicU64 = AtomicU64::new(0);
+
+    if hi <= lo {
+        return lo;
+    }
+    let mut x = STATE.load(Ordering::Relaxed);
+    if x == 0 {
+        x = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15)
+            | 1;
+    }
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    STATE.store(x, Ordering::Relaxed);
+    lo + x % (hi - lo + 1)
+}
+ 
+/// Current cursor position (screen px).
+fn get_mouse_location(display: &str) -> Result<(i32, i32)> {
+    let out = Command::new("xdotool")
+        .env("DISPLAY", display)
+        .args(["getmouselocation", "--shell"])
+        .output()
+        .context("failed to run xdotool getmouselocation")?;
+    if !out.status.success() {
+        bail!("xdotool getmouselocation failed: {}", String::from_utf8_lossy(&out.stderr));
+    }
+    let s = String::from_utf8_lossy(&out.stdout);
+    let mut x = 0i32;
+    let mut y = 0i32;
+    for line in s.lines() {
+        if let Some(v) = line.strip_prefix("X=") { x = v.parse()?; }
+        if let Some(v) = line.strip_prefix("Y=") { y = v.parse()?; }
+    }
+    Ok((x, y))
+}
+ 
+/// Glide from the current cursor position to (x, y) in a few eased steps.
+fn humanized_move(display: &str, x: i32, y: i32) -> Result<()> {
+    let (sx, sy) = get_mouse_location(display).unwrap_or((x, y));
+    let steps = jitter_between(6, 12) as i32;
+    for i in 1..=steps {
+        // ease-out: fast start, slow approach
+        let t = i as f64 / steps as f64;
+        let e = 1.0 - (1.0 - t).powi(2);
+        let px = sx + ((x - sx) as f64 * e).round() as i32;
+        let py = sy + ((y - sy) as f64 * e).round() as i32;
+        let status = Command::new("xdotool")
+            .env("DISPLAY", display)
+            .args(["mousemove", "--sync", &px.to_string(), &py.to_string()])
+            .status()
+            .context("xdotool mousemove failed")?;
+        if !status.success() {
+            bail!("xdotool mousemove returned non-zero status");
+        }
+        std::thread::sleep(std::time::Duration::from_millis(jitter_between(8, 25)));
+    }
+    Ok(())
+}
+ 
 /// Physical X display size (px).
 pub fn get_display_geometry(display: &str) -> Result<(i32, i32)> {
     let out = Command::new("xdotool")
@@ -58,8 +135,69 @@ pub fn get_active_window_geometry(display: &str) -> Result<(i32, i32, i32, i32)>
     Ok((x, y, w, h))
 }
  
+/// `get_active_window_geometry`, retried twice after a short sleep (nothing may be
+/// focused yet on a fresh desktop). If it still fails, the whole display is treated
+/// as the window at (0, 0) — right for kiosk/fullscreen Chrome, approximate otherwise.
+pub fn get_window_or_display_geometry(display: &str) -> Result<(i32, i32, i32, i32)> {
+    let mut last_err = None;
+    for attempt in 1..=3 {
+        match get_active_window_geometry(display) {
+            Ok(g) if g.2 > 0 && g.3 > 0 => return Ok(g),
+            Ok(g) => last_err = Some(anyhow::anyhow!("active window has empty geometry {g:?}")),
+            Err(e) => last_err = Some(e),
+        }
+        if attempt < 3 {
+            std::thread::sleep(std::time::Duration::from_millis(200 * attempt as u64));
+        }
+    }
+
+    let (w, h) = get_display_geometry(display)?;
+    eprintln!(
+        "⚠️ active window geometry unavailable ({:#}); using the full {w}x{h} display",
+        last_err.unwrap_or_else(|| anyhow::anyhow!("unknown error"))
+    );
+    Ok((0, 0, w, h))
+}
+
+/// Raise and focus the Chrome window so `getactivewindow` geometry is Chrome's.
+/// Matches visible windows whose name matches CHROME_WINDOW_NAME (regex, default
+/// "Chrome|Chromium"); when several match, the last one `xdotool search` lists wins.
+pub fn focus_chrome_window(display: &str) -> Result<()> {
+    let pattern = std::env::var("CHROME_WINDOW_NAME").unwrap_or_else(|_| "Chrome|Chromium".to_string());
+    let out = Command::new("xdotool")
+        .env("DISPLAY", display)
+        .args(["search", "--onlyvisible", "--name", &pattern])
+        .output()
+        .context("failed to run xdotool search")?;
+
+    let s = String::from_utf8_lossy(&out.stdout);
+    let Some(window_id) = s.lines().map(str::trim).filter(|l| !l.is_empty()).next_back() else {
+        bail!("no visible window matching {pattern:?}");
+    };
+
+    let status = Command::new("xdotool")
+        .env("DISPLAY", display)
+        .args(["windowactivate", "--sync", window_id])
+        .status()
+        .context("xdotool windowactivate failed")?;
+    if !status.success() {
+        bail!("xdotool windowactivate returned non-zero status");
+    }
+    Ok(())
+}
+ 
 /// Move the OS cursor and click (optionally double).
 pub fn xdotool_move_and_click(display: &str, x: i32, y: i32, double: bool) -> Result<()> {
+    if dry_run_enabled() {
+        println!("[DRY_RUN] would click at screen ({x}, {y}) double={double}");
+        return Ok(());
+    }
+
+    if humanize_clicks_enabled() {
+        humanized_move(display, x, y)?;
+        std::thread::sleep(std::time::Duration::from_millis(jitter_between(40, 160)));
+    }
+
     let status = Command::new("xdotool")
         .env("DISPLAY", display)
         .args(["mousemove", "--sync", &x.to_string(), &y.to_string()])
@@ -90,6 +228,54 @@ pub fn xdotool_move_and_click(display: &str, x: i32, y: i32, double: bool) -> Re
     }
     Ok(())
 }
+
+/// Press at `from`, move to `to` in DRAG_STEPS (default 12) interpolated moves, release.
+/// Widgets that track pointermove (sliders, sortable lists) need the intermediate events.
+pub async fn xdotool_drag(display: &str, from: (i32, i32), to: (i32, i32)) -> Result<()> {
+    if dry_run_enabled() {
+        println!("[DRY_RUN] would drag from screen {from:?} to {to:?}");
+        return Ok(());
+    }
+
+    let steps: i32 = std::env::var("DRAG_STEPS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(12)
+        .max(1);
+
+    let xdo = |args: &[&str], what: &str| -> Result<()> {
+        let status = Command::new("xdotool")
+            .env("DISPLAY", display)
+            .args(args)
+            .status()
+            .with_context(|| format!("xdotool {what} failed"))?;
+        if !status.success() {
+            bail!("xdotool {what} returned non-zero status");
+        }
+        Ok(())
+    };
+
+    xdo(&["mousemove", "--sync", &from.0.to_string(), &from.1.to_string()], "mousemove")?;
+    tokio::time::sleep(std::time::Duration::from_millis(80)).await;
+    xdo(&["mousedown", "1"], "mousedown")?;
+
+    let mut result = Ok(());
+    for i in 1..=steps {
+        let t = i as f64 / steps as f64;
+        let px = from.0 + ((to.0 - from.0) as f64 * t).round() as i32;
+        let py = from.1 + ((to.1 - from.1) as f64 * t).round() as i32;
+        result = xdo(&["mousemove", "--sync", &px.to_string(), &py.to_string()], "mousemove");
+        if result.is_err() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(jitter_between(15, 35))).await;
+    }
+
+    // Always release, even if a move failed, so the button isn't left held down
+    tokio::time::sleep(std::time::Duration::from_millis(80)).await;
+    let released = xdo(&["mouseup", "1"], "mouseup");
+    result.and(released)
+}
  
 /// Send Ctrl+0 to reset browser zoom to 100% (no JS).
 pub fn reset_zoom(display: &str) -> Result<()> {
@@ -106,3 +292,40 @@ pub fn reset_zoom(display: &str) -> Result<()> {
     Ok(())
 }
  
+
+/// True when SCREENSHOT_SOURCE=display: vision clicks capture the whole X display (so
+/// OS-native print dialogs and file pickers are visible) instead of the browser viewport.
+pub fn display_screenshot_source() -> bool {
+    std::env::var("SCREENSHOT_SOURCE").map_or(false, |v| v.eq_ignore_ascii_case("display"))
+}
+
+/// PNG of the whole X display via ImageMagick `import -window root`, else `scrot`.
+pub fn capture_display_png(display: &str) -> Result<Vec<u8>> {
+    if which("import").is_ok() {
+        let out = Command::new("import")
+            .env("DISPLAY", display)
+            .args(["-window", "root", "png:-"])
+            .output()
+            .context("failed to run import")?;
+        if out.status.success() && !out.stdout.is_empty() {
+            return Ok(out.stdout);
+        }
+        eprintln!("⚠️ import -window root failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    if which("scrot").is_ok() {
+        let tmp = std::env::temp_dir().join(format!("display-{}.png", std::process::id()));
+        let _ = std::fs::remove_file(&tmp);
+        let status = Command::new("scrot")
+            .env("DISPLAY", display)
+            .arg(&tmp)
+            .status()
+            .context("failed to run scrot")?;
+        if status.success() {
+            let png = std::fs::read(&tmp).context("read scrot capture")?;
+            let _ = std::fs::remove_file(&tmp);
+            return Ok(png);
+        }
+        eprintln!("⚠️ scrot returned non-zero status");
+    }
+    bail!("SCREENSHOT_SOURCE=display needs ImageMagick `import` or `scrot` (e.g., apt-get install imagemagick)")
+}
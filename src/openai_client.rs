@@ -2,9 +2,11 @@ use anyhow::{Context, Result};
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use thirtyfour::prelude::*;
+use thirtyfour::components::SelectElement;
 use std::env;
 use std::time::Duration;
 use crate::overlay::{overlay_grid_with_coords, GridOptions};
+use crate::mouse::{display_screenshot_source, dry_run_enabled};
 
 // --- drawing + saving imports ---
 use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
@@ -12,7 +14,9 @@ use std::fs;
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::task::JoinSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Mutex, OnceLock};
+use std::collections::HashMap;
 
 
 // NEW: simple in-process memory of previously chosen points
@@ -74,22 +78,220 @@ where
     f(&mut tracker)
 }
 
+/// OpenAI requests made by this process (every sample, DOM decision and yes/no question).
+static LLM_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn llm_requests_made() -> usize {
+    LLM_REQUESTS.load(Ordering::Relaxed)
+}
+
+/// Process-wide cap on in-flight point requests (OPENAI_GLOBAL_CONCURRENCY, default 4).
+/// OPENAI_MAX_CONCURRENCY only bounds one call's samples; overlapping calls share this.
+fn global_request_permits() -> &'static tokio::sync::Semaphore {
+    static PERMITS: OnceLock<tokio::sync::Semaphore> = OnceLock::new();
+    PERMITS.get_or_init(|| {
+        let n: usize = env::var("OPENAI_GLOBAL_CONCURRENCY")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(4)
+            .max(1);
+        tokio::sync::Semaphore::new(n)
+    })
+}
+
+/// Count one HTTP request (each retry included) against LLM_MAX_REQUESTS (unset/0 =
+/// unlimited). Once the budget is spent every further call fails, so a looping plan
+/// cannot keep burning quota.
+fn reserve_llm_request() -> Result<(), OpenAiError> {
+    let limit: usize = env::var("LLM_MAX_REQUESTS").ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let made = LLM_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    if limit > 0 && made >= limit {
+        LLM_REQUESTS.fetch_sub(1, Ordering::Relaxed);
+        return Err(OpenAiError::BudgetExhausted { limit });
+    }
+    Ok(())
+}
+
+/// Typed failures from the point-selection calls, so callers can decide whether a
+/// retry of the whole step is worthwhile. Converts into anyhow::Error via `?`.
+#[derive(Debug)]
+pub enum OpenAiError {
+    /// Every attempt got HTTP 429
+    RateLimited { attempts: usize },
+    /// The model replied but the content was not the JSON we asked for
+    ParseFailed { raw: String, reason: String },
+    /// Non-success, non-429 HTTP status
+    Http { status: u16, body: String },
+    /// Connection / timeout / body-read failure
+    Transport(reqwest::Error),
+    /// call_openai_for_point: no sample produced a point
+    AllSamplesFailed { samples: usize, rate_limited: usize },
+    /// The aggregated point fell outside the screenshot twice
+    OutOfBounds { x: i32, y: i32, width: u32, height: u32 },
+    /// LLM_MAX_REQUESTS reached; no request was sent
+    BudgetExhausted { limit: usize },
+    /// Local failures (e.g. decoding the screenshot for the grid overlay)
+    Other(anyhow::Error),
+}
+
+impl std::fmt::Display for OpenAiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited { attempts } => {
+                write!(f, "OpenAI request failed (rate limited after {attempts} attempts)")
+            }
+            Self::ParseFailed { raw, reason } => {
+                write!(f, "Failed to parse JSON from OpenAI: {reason}\nRaw content: {raw}")
+            }
+            Self::Http { status, body } => write!(f, "OpenAI HTTP {status}: {body}"),
+            Self::Transport(e) => write!(f, "OpenAI transport error: {e}"),
+            Self::AllSamplesFailed { samples, rate_limited } => write!(
+                f,
+                "All {samples} OpenAI samples failed ({rate_limited} rate-limit related)"
+            ),
+            Self::OutOfBounds { x, y, width, height } => write!(
+                f,
+                "OpenAI point ({x}, {y}) is outside the {width}x{height} screenshot"
+            ),
+            Self::BudgetExhausted { limit } => write!(
+                f,
+                "OpenAI request budget exhausted (LLM_MAX_REQUESTS={limit}); refusing further calls"
+            ),
+            Self::Other(e) => write!(f, "{e:#}"),
+        }
+    }
+}
+
+impl std::error::Error for OpenAiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl OpenAiError {
+    /// 429s, or an all-failed batch where at least one sample was rate limited.
+    pub fn is_rate_limit(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } => true,
+            Self::Http { status, .. } => *status == 429,
+            Self::AllSamplesFailed { rate_limited, .. } => *rate_limited > 0,
+            _ => false,
+        }
+    }
+}
+
+/// Output rules appended to a custom system prompt (LLM_SYSTEM_PROMPT) for point calls.
+const POINT_JSON_CONTRACT: &str = "Output ONLY JSON (no markdown fences, no prose) with keys \
+    x:int,y:int,double:bool,confidence:number 0..1. Coordinates are CSS/viewport pixels relative to the visible page (top-left).";
+
+/// Output rules appended to a custom system prompt for DOM decisions.
+const DOM_JSON_CONTRACT: &str = "Choose exactly one candidate. Respond ONLY with JSON in this exact \
+    shape: {\"id\": <number>, \"reason\": \"...\", \"confidence\": <number 0..1>}. A candidate \
+    with \"occurrences\" > 1 stands for that many identical controls in page order; to pick a \
+    specific one, also add \"nth\": <1-based>.";
+
+/// How requests are addressed and authenticated (OPENAI_API_STYLE=openai|azure).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiStyle {
+    /// `{base}/chat/completions` with a bearer token
+    OpenAi,
+    /// `{base}/openai/deployments/{model}/chat/completions?api-version=...` with an
+    /// `api-key` header; `model` is the deployment name
+    Azure { api_version: String },
+}
+
 #[derive(Debug, Clone)]
 pub struct OpenAIConfig {
     pub api_key: String,
     pub base_url: String, // default official; override for proxies/azure
-    pub model: String,    // e.g., "gpt-4o-mini"
+    pub model: String,    // e.g., "gpt-4o-mini" (Azure: deployment name)
     pub timeout: Duration,
     pub max_retries: usize,
+    pub api_style: ApiStyle,
+    /// Portal-specific guidance replacing the built-in system prompts
+    /// (LLM_SYSTEM_PROMPT, or the contents of LLM_SYSTEM_PROMPT_FILE)
+    pub system_prompt_override: Option<String>,
+    /// OPENAI_PROXY, else HTTPS_PROXY; applied to every OpenAI request
+    pub proxy: Option<String>,
+    /// OPENAI_EXTRA_HEADERS (`k:v,k:v`), e.g. OpenAI-Organization
+    pub extra_headers: reqwest::header::HeaderMap,
+}
+
+/// `Name: value, Other-Name: value` → headers. Malformed entries are an error, not skipped,
+/// so a typo can't silently drop e.g. the organization header.
+fn parse_extra_headers(raw: &str) -> Result<reqwest::header::HeaderMap> {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+    let mut headers = HeaderMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, value) = entry
+            .split_once(':')
+            .with_context(|| format!("OPENAI_EXTRA_HEADERS entry '{entry}' is not name:value"))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .with_context(|| format!("OPENAI_EXTRA_HEADERS: bad header name '{}'", name.trim()))?;
+        let value = HeaderValue::from_str(value.trim())
+            .with_context(|| format!("OPENAI_EXTRA_HEADERS: bad value for '{name}'"))?;
+        headers.insert(name, value);
+    }
+    Ok(headers)
 }
 
 impl OpenAIConfig {
+    /// `from_env` for runs where OpenAI is optional: Ok(None) when no API key is configured
+    /// (heuristic-only run); any other config error is returned.
+    pub fn from_env_optional() -> Result<Option<Self>> {
+        if crate::secrets::secret_from_env("OPENAI_API_KEY")?.is_none() {
+            return Ok(None);
+        }
+        Self::from_env().map(Some)
+    }
+
     pub fn from_env() -> Result<Self> {
+        let api_style = match env::var("OPENAI_API_STYLE").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "" | "openai" => ApiStyle::OpenAi,
+            "azure" => ApiStyle::Azure {
+                api_version: env::var("OPENAI_API_VERSION").unwrap_or_else(|_| "2024-06-01".to_string()),
+            },
+            other => anyhow::bail!("Unknown OPENAI_API_STYLE '{other}' (expected openai|azure)"),
+        };
+        let base_url = match (&api_style, env::var("OPENAI_BASE_URL")) {
+            (_, Ok(url)) => url.trim_end_matches('/').to_string(),
+            (ApiStyle::OpenAi, Err(_)) => "https://api.openai.com/v1".to_string(),
+            (ApiStyle::Azure { .. }, Err(_)) => {
+                anyhow::bail!("OPENAI_API_STYLE=azure needs OPENAI_BASE_URL (https://<resource>.openai.azure.com)")
+            }
+        };
+        let system_prompt_override = match env::var("LLM_SYSTEM_PROMPT_FILE") {
+            Ok(path) if !path.trim().is_empty() => Some(
+                fs::read_to_string(&path).with_context(|| format!("cannot read LLM_SYSTEM_PROMPT_FILE {path}"))?,
+            ),
+            _ => env::var("LLM_SYSTEM_PROMPT").ok(),
+        }
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+
+        let proxy = env::var("OPENAI_PROXY")
+            .or_else(|_| env::var("HTTPS_PROXY"))
+            .or_else(|_| env::var("https_proxy"))
+            .ok()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        if let Some(p) = &proxy {
+            reqwest::Proxy::all(p).with_context(|| format!("invalid OpenAI proxy URL '{p}'"))?;
+        }
+        let extra_headers = parse_extra_headers(&env::var("OPENAI_EXTRA_HEADERS").unwrap_or_default())?;
+
         Ok(Self {
-            api_key: env::var("OPENAI_API_KEY")
-                .context("Set OPENAI_API_KEY in your environment")?,
-            base_url: env::var("OPENAI_BASE_URL")
-                .unwrap_or_else(|_| "https://api.openai.com/v1".to_string()),
+            system_prompt_override,
+            proxy,
+            extra_headers,
+            api_key: crate::secrets::secret_from_env("OPENAI_API_KEY")?
+                .context("Set OPENAI_API_KEY_FILE (or OPENAI_API_KEY) in your environment")?,
+            base_url,
+            api_style,
             model: env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string()),
             timeout: Duration::from_secs(
                 env::var("OPENAI_TIMEOUT_SECS")
@@ -103,6 +305,47 @@ impl OpenAIConfig {
                 .unwrap_or(3),
         })
     }
+
+    /// The system prompt for a call: `default` unless an override is configured, in which
+    /// case the override is used with the call's JSON `contract` always appended, so custom
+    /// guidance cannot break response parsing.
+    fn system_prompt(&self, default: &str, contract: &str) -> String {
+        match &self.system_prompt_override {
+            Some(custom) => format!("{custom}\n\n{contract}"),
+            None => default.to_string(),
+        }
+    }
+
+    /// HTTP client for OpenAI calls: request timeout, optional proxy, extra headers.
+    fn http_client(&self) -> reqwest::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .default_headers(self.extra_headers.clone());
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        builder.build()
+    }
+
+    /// Chat completions endpoint for this API style.
+    fn chat_url(&self) -> String {
+        match &self.api_style {
+            ApiStyle::OpenAi => format!("{}/chat/completions", self.base_url),
+            ApiStyle::Azure { api_version } => format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                self.base_url, self.model, api_version
+            ),
+        }
+    }
+
+    /// POST to the chat endpoint with this style's auth header.
+    fn post_chat(&self, client: &reqwest::Client) -> reqwest::RequestBuilder {
+        let req = client.post(self.chat_url());
+        match self.api_style {
+            ApiStyle::OpenAi => req.bearer_auth(&self.api_key),
+            ApiStyle::Azure { .. } => req.header("api-key", &self.api_key),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -168,13 +411,16 @@ struct ChoiceMessage {
     content: String,
 }
 
-/// Model returns JSON {x:int, y:int, double:bool} in viewport pixels.
-#[derive(Deserialize, Debug, Clone, Copy)]
+/// Model returns JSON {x:int, y:int, double:bool, confidence?:0..1} in viewport pixels.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct ViewportPoint {
     pub x: i32,
     pub y: i32,
     #[serde(default)]
     pub double: bool,
+    /// Model's own certainty for this sample; weights the IQR mean when every sample has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
 }
 
 //TODO NEW UI CLICK FUNCTIONALITY ADDENDEUM
@@ -215,12 +461,22 @@ pub struct UiCandidate {
     pub name: String,
     pub value: String,
     pub data_test: String,
+    /// How many visible elements share this tag/text/aria (repeats are folded into one
+    /// candidate; the model can pick one with `nth`)
+    #[serde(skip_serializing_if = "is_single")]
+    pub occurrences: usize,
+}
+
+fn is_single(n: &usize) -> bool {
+    *n <= 1
 }
 
 #[derive(Debug, Clone)]
 pub struct Candidate {
     pub meta: UiCandidate,
     pub el: WebElement,
+    /// Later elements with the same tag/text/aria, in page order (occurrence 2, 3, ...)
+    pub repeats: Vec<WebElement>,
     // New: shape info for heuristic fallback
     pub rect: Option<(i32, i32, i32, i32)>, // x,y,w,h
     pub visible: bool,
@@ -230,12 +486,43 @@ pub struct Candidate {
 #[derive(Debug, Deserialize)]
 pub(crate) struct ClickDecision {
     id: Option<usize>,
-    _reason: Option<String>,
-    _confidence: Option<f32>,
+    reason: Option<String>,
+    confidence: Option<f32>,
+    /// 1-based occurrence of a repeated candidate (see UiCandidate::occurrences)
+    #[serde(default)]
+    nth: Option<usize>,
 }
 
+/// What `click_by_llm_dom_first` ended up clicking and how sure it was.
+#[derive(Debug, Clone, Copy)]
+pub struct ClickOutcome {
+    pub chosen_idx: usize,
+    /// Model-reported confidence (None when the heuristic picked or the model omitted it)
+    pub confidence: Option<f32>,
+    /// True when the LLM call failed or returned an invalid id and the heuristic chose
+    pub used_fallback: bool,
+    /// 1-based occurrence among the chosen candidate's repeats (None = the first)
+    pub nth: Option<usize>,
+}
+
+/// Collect up to `cap` visible, enabled candidates, deduplicated by (tag, text, aria).
+/// Up to DOM_SCAN_CAP (default 1500) matched elements are inspected so that a long run of
+/// hidden nav links early in DOM order can't push real buttons past `cap`.
 pub async fn collect_ui_candidates(driver: &WebDriver, cap: usize) -> Result<Vec<Candidate>> {
-    let selectors = [
+    let elems = driver.find_all(By::Css(candidate_selectors())).await?;
+    candidates_from_elements(elems, cap).await
+}
+
+/// Same as `collect_ui_candidates`, limited to descendants of `root` (e.g. one table row).
+/// Checkboxes and their labels are included here since row controls are often just that.
+pub async fn collect_ui_candidates_within(root: &WebElement, cap: usize) -> Result<Vec<Candidate>> {
+    let selectors = format!("{},label,input[type='checkbox']", candidate_selectors());
+    let elems = root.find_all(By::Css(&selectors)).await?;
+    candidates_from_elements(elems, cap).await
+}
+
+fn candidate_selectors() -> String {
+    [
         "button",
         "a[href]",
         "[role='button']",
@@ -255,35 +542,87 @@ pub async fn collect_ui_candidates(driver: &WebDriver, cap: usize) -> Result<Vec
         "[data-test='select-trigger']",
         "[data-test='shared-section__dropdown-list-item']",
     ]
-    .join(",");
+    .join(",")
+}
 
-    let elems = driver.find_all(By::Css(&selectors)).await?;
-    let mut out = Vec::with_capacity(elems.len().min(cap));
+/// The node was detached/re-rendered between `find_all` and reading it.
+fn is_stale(e: &WebDriverError) -> bool {
+    matches!(e, WebDriverError::StaleElementReference(_))
+}
 
-    for (i, el) in elems.into_iter().enumerate().take(cap) {
-        // Basic attributes
-        let tag = el.tag_name().await.unwrap_or_default().to_uppercase();
-        let text = clean(el.text().await.unwrap_or_default());
-        let aria = clean(el.attr("aria-label").await?.unwrap_or_default());
-        let role = clean(el.attr("role").await?.unwrap_or_default());
-        let ty = clean(el.attr("type").await?.unwrap_or_default());
-        let name = clean(el.attr("name").await?.unwrap_or_default());
-        let value = clean(el.attr("value").await?.unwrap_or_default());
+async fn candidates_from_elements(elems: Vec<WebElement>, cap: usize) -> Result<Vec<Candidate>> {
+    let scan_cap: usize = env::var("DOM_SCAN_CAP")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1500)
+        .max(cap);
 
+    let scanned = elems.len().min(scan_cap);
+    let mut out: Vec<Candidate> = Vec::with_capacity(elems.len().min(cap));
+    let mut seen: HashMap<(String, String, String), usize> = HashMap::new();
+    let mut stale = 0usize;
 
+    for el in elems.into_iter().take(scan_cap) {
+        if out.len() >= cap {
+            break;
+        }
 
-        // Some apps use many variants of data-test
-        
-	let data_test = {
-    	    let d1 = el.attr("data-test").await?;    // Option<String>
-    	    let d2 = el.attr("data-testid").await?;  // Option<String>
-            let d3 = el.attr("data-qa").await?;      // Option<String>
-    	    d1.or(d2).or(d3).unwrap_or_default()
-	};
-
-        // State/visibility
+        // State/visibility first: cheap, and most of the noise is hidden or disabled
         let visible = el.is_displayed().await.unwrap_or(false);
-        let disabled = el.attr("disabled").await?.is_some();
+        if !visible {
+            continue;
+        }
+        let basics = async {
+            let disabled = el.attr("disabled").await?.is_some();
+            let tag = el.tag_name().await.unwrap_or_default().to_uppercase();
+            let text = clean(el.text().await.unwrap_or_default());
+            let aria = clean(el.attr("aria-label").await?.unwrap_or_default());
+            Ok::<_, WebDriverError>((disabled, tag, text, aria))
+        }
+        .await;
+        let (disabled, tag, text, aria) = match basics {
+            Ok(b) => b,
+            Err(e) if is_stale(&e) => {
+                stale += 1;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if disabled {
+            continue;
+        }
+
+        // Unlabelled elements (icon buttons) are kept; they differ by data-test / position.
+        // Labelled repeats fold into the first one so `nth` can still reach them.
+        let key = (!(text.is_empty() && aria.is_empty())).then(|| (tag.clone(), text.clone(), aria.clone()));
+        if let Some(&first) = key.as_ref().and_then(|k| seen.get(k)) {
+            out[first].repeats.push(el);
+            out[first].meta.occurrences += 1;
+            continue;
+        }
+
+        let details = async {
+            let role = clean(el.attr("role").await?.unwrap_or_default());
+            let ty = clean(el.attr("type").await?.unwrap_or_default());
+            let name = clean(el.attr("name").await?.unwrap_or_default());
+            let value = clean(el.attr("value").await?.unwrap_or_default());
+
+            // Some apps use many variants of data-test
+            let d1 = el.attr("data-test").await?;
+            let d2 = el.attr("data-testid").await?;
+            let d3 = el.attr("data-qa").await?;
+            let data_test = d1.or(d2).or(d3).unwrap_or_default();
+            Ok::<_, WebDriverError>((role, ty, name, value, data_test))
+        }
+        .await;
+        let (role, ty, name, value, data_test) = match details {
+            Ok(d) => d,
+            Err(e) if is_stale(&e) => {
+                stale += 1;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         // Geometry (best-effort)
         let rect = match el.rect().await {
@@ -291,9 +630,14 @@ pub async fn collect_ui_candidates(driver: &WebDriver, cap: usize) -> Result<Vec
             Err(_) => None,
         };
 
+        // Registered only once the candidate is really pushed, so a stale skip above
+        // can't leave `seen` pointing at the wrong index
+        if let Some(k) = key {
+            seen.insert(k, out.len());
+        }
         out.push(Candidate {
             meta: UiCandidate {
-                id: i,
+                id: out.len(), // index into the returned list (what the model answers with)
                 tag,
                 text,
                 aria,
@@ -302,13 +646,19 @@ pub async fn collect_ui_candidates(driver: &WebDriver, cap: usize) -> Result<Vec
                 name,
                 value,
                 data_test,
+                occurrences: 1,
             },
             el,
+            repeats: Vec::new(),
             rect,
             visible,
             disabled,
         });
     }
+    if stale > 0 {
+        println!("[collect_ui_candidates] skipped {stale} element(s) that went stale mid-read");
+    }
+    println!("[collect_ui_candidates] {} candidates from {scanned} scanned elements", out.len());
     Ok(out)
 }
 
@@ -367,6 +717,44 @@ pub async fn click_stage_option(driver: &WebDriver, name: &str) -> Result<()> {
 
 
 
+/// Choose an option in a native <select>: exact visible text first,
+/// then a case-insensitive "contains" match.
+pub async fn select_dropdown_option(
+    driver: &WebDriver,
+    selector: &str,
+    option_text: &str,
+) -> Result<()> {
+    let select_el = driver
+        .find(By::Css(selector))
+        .await
+        .with_context(|| format!("Could not find <select> matching {selector}"))?;
+    let select = SelectElement::new(&select_el).await?;
+
+    if select.select_by_visible_text(option_text).await.is_ok() {
+        println!("✔ Selected option {:?} in {}", option_text, selector);
+        return Ok(());
+    }
+
+    let wanted = option_text.trim().to_lowercase();
+    for opt in select.options().await? {
+        let text = opt.text().await.unwrap_or_default();
+        if text.trim().to_lowercase().contains(&wanted) {
+            opt.click().await?;
+            println!(
+                "✔ Selected option {:?} in {} (contains match for {:?})",
+                text.trim(), selector, option_text
+            );
+            return Ok(());
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "No option matching {:?} in <select> {}",
+        option_text,
+        selector
+    ))
+}
+
 pub async fn click_invoice_amount_input(driver: &WebDriver) -> Result<()> {
     // Find the amount input box
     let amount_input = driver
@@ -538,17 +926,19 @@ pub async fn call_openai_for_dom_decision(
         with_rate_limit_tracker(|tracker| tracker.record_success());
     }
 
-    let client = reqwest::Client::builder().timeout(cfg.timeout).build()?;
+    let client = cfg.http_client()?;
 
     // Keep the message contract the same but a tad stricter about JSON
     let system = ChatMessage {
         role: "system",
-        content: ChatContent::Text(
+        content: ChatContent::Text(cfg.system_prompt(
             "You are a UI clicking assistant. Choose exactly one candidate that best \
              matches the user's intent. Respond ONLY with JSON in this exact shape: \
-             {\"id\": <number>, \"reason\": \"...\", \"confidence\": <number 0..1>}"
-                .to_string(),
-        ),
+             {\"id\": <number>, \"reason\": \"...\", \"confidence\": <number 0..1>}. \
+             A candidate with \"occurrences\" > 1 stands for that many identical controls \
+             in page order; if the user wants a specific one, also add \"nth\": <1-based>.",
+            DOM_JSON_CONTRACT,
+        )),
     };
 
     // We pass a compact list — if you want, you can add extra fields
@@ -569,14 +959,14 @@ pub async fn call_openai_for_dom_decision(
         messages: vec![system, user],
     };
 
-    let url = format!("{}/chat/completions", cfg.base_url);
     let mut last_err: Option<anyhow::Error> = None;
     let mut rate_limited = false;
 
     for attempt in 0..cfg.max_retries {
-        let resp = client
-            .post(&url)
-            .bearer_auth(&cfg.api_key)
+        // Every POST counts against LLM_MAX_REQUESTS, retries included
+        reserve_llm_request()?;
+        let resp = cfg
+            .post_chat(&client)
             .json(&req_body)
             .send()
             .await;
@@ -646,7 +1036,36 @@ pub async fn call_openai_for_dom_decision(
 
 // ---------- Heuristic fallback (deterministic) ----------
 
-fn rank_score(prompt: &str, c: &UiCandidate, rect: Option<(i32,i32,i32,i32)>) -> f32 {
+/// Weighted contributions of each `rank_score` feature; `total()` is the ranking key.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ScoreBreakdown {
+    /// Prompt words (len >= 3) found in text/aria, 1.0 each
+    hits: f32,
+    /// Button tag and send/submit/save nudges
+    sem: f32,
+    /// sqrt(area)/60 capped at 1, weighted 0.6
+    size: f32,
+    /// Closeness to the viewport centre, 0..0.5
+    center: f32,
+}
+
+impl ScoreBreakdown {
+    fn total(&self) -> f32 {
+        self.hits + self.sem + self.size + self.center
+    }
+}
+
+impl std::fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.3} = hits {:.2} + sem {:.2} + size {:.2} + center {:.2}",
+            self.total(), self.hits, self.sem, self.size, self.center
+        )
+    }
+}
+
+fn rank_score(prompt: &str, c: &UiCandidate, rect: Option<(i32,i32,i32,i32)>) -> ScoreBreakdown {
     // Simple, explainable scoring
     let p = prompt.to_lowercase();
     let t = c.text.to_lowercase();
@@ -681,12 +1100,12 @@ fn rank_score(prompt: &str, c: &UiCandidate, rect: Option<(i32,i32,i32,i32)>) ->
         center = (1.0 - (dist / 1100.0)).clamp(0.0, 0.5);
     }
 
-    hits * 1.0 + sem + size * 0.6 + center
+    ScoreBreakdown { hits: hits * 1.0, sem, size: size * 0.6, center }
 }
 
 fn choose_best_by_heuristic(prompt: &str, cands: &[Candidate]) -> usize {
     // Filter visible & enabled
-    let mut scored: Vec<(usize, f32, i32)> = Vec::new(); // (idx, score, area)
+    let mut scored: Vec<(usize, ScoreBreakdown, i32)> = Vec::new(); // (idx, score, area)
     for (i, c) in cands.iter().enumerate() {
         if !c.visible || c.disabled {
             continue;
@@ -704,57 +1123,365 @@ fn choose_best_by_heuristic(prompt: &str, cands: &[Candidate]) -> usize {
     // Sort: score desc, area desc, id asc (deterministic)
     scored.sort_by(|a, b| {
         use std::cmp::Ordering::*;
-        b.1.partial_cmp(&a.1).unwrap_or(Equal)
+        b.1.total().partial_cmp(&a.1.total()).unwrap_or(Equal)
             .then(b.2.cmp(&a.2))
             .then(a.0.cmp(&b.0))
     });
 
+    // The runners-up too, so a wrong pick shows which feature outweighed the right one
+    let shown: usize = env::var("HEURISTIC_LOG_TOP")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(3);
+    for (rank, (i, score, _)) in scored.iter().take(shown).enumerate() {
+        println!(
+            "(fallback) #{} idx={i} {:?} aria={:?}: {score}",
+            rank + 1, cands[*i].meta.text, cands[*i].meta.aria
+        );
+    }
+
     let (best, best_s, _) = scored[0];
-    println!("(fallback) chose #{best} with score {:.3}", best_s);
+    println!("(fallback) chose #{best} with score {:.3}", best_s.total());
     best
 }
 
-// ---------- Main entry (unchanged signature) ----------
+// ---------- Main entry ----------
 
+/// DOM_HEURISTIC_ONLY=1 skips the LLM decision and clicks the top `rank_score` candidate.
+pub fn heuristic_only_enabled() -> bool {
+    env::var("DOM_HEURISTIC_ONLY").map_or(false, |v| v == "1")
+}
+
+/// Pick a DOM candidate for `user_prompt` and click it. With `cfg` = None (no OpenAI key)
+/// or DOM_HEURISTIC_ONLY=1 the heuristic scorer decides without calling the model.
+/// `nth` (1-based) clicks that occurrence of a repeated control, overriding the model's.
 pub async fn click_by_llm_dom_first(
     driver: &WebDriver,
-    cfg: &OpenAIConfig,
+    cfg: Option<&OpenAIConfig>,
     user_prompt: &str,
     force_double: Option<bool>,
-) -> Result<()> {
+    nth: Option<usize>,
+) -> Result<ClickOutcome> {
     let cands = collect_ui_candidates(driver, 200).await?;
     if cands.is_empty() {
         anyhow::bail!("No clickable candidates found on page");
     }
+    let mut outcome = choose_candidate(&cands, cfg, user_prompt).await?;
+    if nth.is_some() {
+        outcome.nth = nth;
+    }
+    click_candidate(&cands, outcome, force_double).await
+}
+
+/// How `click_by_text` compares a candidate's text/aria-label with the wanted text
+/// (both trimmed, case-insensitive).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum TextMatch {
+    #[default]
+    #[serde(alias = "exact")]
+    Exact,
+    #[serde(alias = "contains")]
+    Contains,
+}
+
+impl TextMatch {
+    fn matches(self, have: &str, want: &str) -> bool {
+        let have = have.trim().to_lowercase();
+        match self {
+            TextMatch::Exact => have == want,
+            TextMatch::Contains => !want.is_empty() && have.contains(want),
+        }
+    }
+}
+
+/// Deterministic click: the single visible, enabled candidate whose text or aria-label
+/// matches `text`. Zero matches, or several without `nth` (1-based, page order), is an
+/// error listing what was found; no model is involved.
+pub async fn click_by_text(
+    driver: &WebDriver,
+    text: &str,
+    mode: TextMatch,
+    force_double: Option<bool>,
+    nth: Option<usize>,
+) -> Result<ClickOutcome> {
+    let want = text.trim().to_lowercase();
+    let cands = collect_ui_candidates(driver, 200).await?;
+
+    // Every matching element, repeats included, as (candidate idx, occurrence)
+    let hits: Vec<(usize, usize)> = cands
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| mode.matches(&c.meta.text, &want) || mode.matches(&c.meta.aria, &want))
+        .flat_map(|(i, c)| (1..=c.meta.occurrences).map(move |k| (i, k)))
+        .collect();
+
+    let (idx, occurrence) = match (hits.len(), nth) {
+        (0, _) => anyhow::bail!("ClickByText: no visible element matches {text:?} ({mode:?})"),
+        (_, Some(n)) => *hits.get(n.saturating_sub(1)).with_context(|| {
+            format!("ClickByText: nth={n} but only {} element(s) match {text:?}", hits.len())
+        })?,
+        (1, None) => hits[0],
+        (found, None) => {
+            let names: Vec<String> = hits
+                .iter()
+                .map(|&(i, _)| format!("{}:{:?}", cands[i].meta.tag, cands[i].meta.text))
+                .collect();
+            anyhow::bail!(
+                "ClickByText: {found} elements match {text:?} ({mode:?}); set nth to pick one: {}",
+                names.join(", ")
+            )
+        }
+    };
+
+    let outcome = ClickOutcome { chosen_idx: idx, confidence: None, used_fallback: false, nth: Some(occurrence) };
+    click_candidate(&cands, outcome, force_double).await
+}
+
+/// Row-scoped DOM click: find the table row (<tr>, [role=row], or the doc-directory row)
+/// whose text contains `row_text` — the most specific match if rows nest — then pick a
+/// control inside it for `control_prompt` with the same LLM/heuristic logic.
+pub async fn click_in_row(
+    driver: &WebDriver,
+    cfg: Option<&OpenAIConfig>,
+    row_text: &str,
+    control_prompt: &str,
+    force_double: Option<bool>,
+) -> Result<ClickOutcome> {
+    let needle = row_text.to_lowercase();
+    let rows = driver
+        .find_all(By::Css(
+            "tr, [role='row'], [data-test='shared-section__docdir-table-row']",
+        ))
+        .await?;
+
+    let mut best: Option<(usize, WebElement)> = None;
+    for row in rows {
+        let text = row.text().await.unwrap_or_default();
+        if text.to_lowercase().contains(&needle)
+            && best.as_ref().map_or(true, |(len, _)| text.len() < *len)
+        {
+            best = Some((text.len(), row));
+        }
+    }
+    let (_, row) = best.ok_or_else(|| anyhow::anyhow!("Could not find row containing: {row_text}"))?;
+
+    let cands = collect_ui_candidates_within(&row, 50).await?;
+    if cands.is_empty() {
+        anyhow::bail!("No clickable controls in row containing: {row_text}");
+    }
+    println!("[click_in_row] row {:?}: {} candidates", row_text, cands.len());
+    decide_and_click(&cands, cfg, control_prompt, force_double).await
+}
+
+/// Hover the DOM candidate best matching `user_prompt` (chosen like `click_by_llm_dom_first`)
+/// by moving the WebDriver pointer to its center, to reveal mouseover-only menus.
+pub async fn hover_by_llm_dom_first(
+    driver: &WebDriver,
+    cfg: Option<&OpenAIConfig>,
+    user_prompt: &str,
+) -> Result<ClickOutcome> {
+    let cands = collect_ui_candidates(driver, 200).await?;
+    if cands.is_empty() {
+        anyhow::bail!("No hoverable candidates found on page");
+    }
+    let outcome = choose_candidate(&cands, cfg, user_prompt).await?;
+    let c = &cands[outcome.chosen_idx];
+
+    if dry_run_enabled() {
+        println!(
+            "[DRY_RUN] would hover: idx={} tag={} text={:?} aria={:?} rect={:?}",
+            outcome.chosen_idx, c.meta.tag, c.meta.text, c.meta.aria, c.rect
+        );
+        return Ok(outcome);
+    }
+
+    let el = occurrence_element(c, outcome.nth);
+    driver.action_chain().move_to_element_center(el).perform().await?;
+    println!(
+        "🖱️ hovered: idx={} tag={} text={:?} aria={:?}",
+        outcome.chosen_idx, c.meta.tag, c.meta.text, c.meta.aria
+    );
+    Ok(outcome)
+}
+
+async fn decide_and_click(
+    cands: &[Candidate],
+    cfg: Option<&OpenAIConfig>,
+    user_prompt: &str,
+    force_double: Option<bool>,
+) -> Result<ClickOutcome> {
+    let outcome = choose_candidate(cands, cfg, user_prompt).await?;
+    click_candidate(cands, outcome, force_double).await
+}
+
+/// Index of the only candidate whose aria-label or text equals `prompt` (trimmed,
+/// case-insensitive). None when there are zero or several such candidates.
+fn exact_name_match(prompt: &str, cands: &[Candidate]) -> Option<usize> {
+    let want = prompt.trim().to_lowercase();
+    if want.is_empty() {
+        return None;
+    }
+    let mut hits = cands.iter().enumerate().filter(|(_, c)| {
+        c.meta.aria.trim().to_lowercase() == want || c.meta.text.trim().to_lowercase() == want
+    });
+    match (hits.next(), hits.next()) {
+        (Some((idx, _)), None) => Some(idx),
+        _ => None,
+    }
+}
+
+/// LLM choice among `cands` with heuristic fallback (cands must be non-empty). Only an
+/// exhausted LLM_MAX_REQUESTS budget is an error; it must stop the run, not degrade it.
+async fn choose_candidate(
+    cands: &[Candidate],
+    cfg: Option<&OpenAIConfig>,
+    user_prompt: &str,
+) -> Result<ClickOutcome> {
+    // Fast path: a prompt that is exactly one candidate's accessible name or text
+    if let Some(idx) = exact_name_match(user_prompt, cands) {
+        println!(
+            "[click_by_llm_dom_first] exact-match fast path: idx={idx} aria={:?} text={:?} (no LLM call)",
+            cands[idx].meta.aria, cands[idx].meta.text
+        );
+        return Ok(ClickOutcome { chosen_idx: idx, confidence: None, used_fallback: false, nth: None });
+    }
+
+    let cfg = match cfg {
+        Some(c) if !heuristic_only_enabled() => c,
+        _ => {
+            let idx = choose_best_by_heuristic(user_prompt, cands);
+            println!("[click_by_llm_dom_first] heuristic-only mode: picked idx={idx} (no LLM call)");
+            return Ok(ClickOutcome { chosen_idx: idx, confidence: None, used_fallback: false, nth: None });
+        }
+    };
 
     // Send a slimmed list to the model (only the serializable UiCandidate)
     let ui_list: Vec<UiCandidate> = cands.iter().map(|c| c.meta.clone()).collect();
     let decision = call_openai_for_dom_decision(cfg, user_prompt, &ui_list).await;
 
     // Resolve index
-    let idx = match decision {
+    let (idx, confidence, used_fallback, nth) = match decision {
         Ok(d) => {
-            /*
-	    println!(
-                "[click_by_llm_dom_first] decision: id={:?} reason={:?} confidence={:?}",
-                d.id, d.reason, d.confidence
+            println!(
+                "[click_by_llm_dom_first] decision: id={:?} nth={:?} reason={:?} confidence={:?}",
+                d.id, d.nth, d.reason, d.confidence
             );
-	    */
             match d.id {
-                Some(i) if i < cands.len() => i,
+                Some(i) if i < cands.len() => (i, d.confidence, false, d.nth),
                 _ => {
                     // invalid id → heuristic
-                    choose_best_by_heuristic(user_prompt, &cands)
+                    (choose_best_by_heuristic(user_prompt, cands), None, true, None)
                 }
             }
         }
+        Err(e) if matches!(e.downcast_ref(), Some(OpenAiError::BudgetExhausted { .. })) => {
+            return Err(e);
+        }
         Err(e) => {
             eprintln!("LLM decision failed → heuristic fallback: {e}");
-            choose_best_by_heuristic(user_prompt, &cands)
+            (choose_best_by_heuristic(user_prompt, cands), None, true, None)
         }
     };
+    Ok(ClickOutcome { chosen_idx: idx, confidence, used_fallback, nth })
+}
+
+/// The element for occurrence `nth` (1-based) of a folded candidate; out-of-range
+/// occurrences fall back to the first with a warning.
+fn occurrence_element(c: &Candidate, nth: Option<usize>) -> &WebElement {
+    match nth {
+        None | Some(0) | Some(1) => &c.el,
+        Some(n) => c.repeats.get(n - 2).unwrap_or_else(|| {
+            eprintln!(
+                "⚠️ nth={n} but {:?} occurs {} time(s); using the first",
+                c.meta.text, c.meta.occurrences
+            );
+            &c.el
+        }),
+    }
+}
+
+pub fn safe_mode_enabled() -> bool {
+    env::var("SAFE_MODE").map_or(false, |v| v == "1")
+}
 
-    let el = &cands[idx].el;
+/// SAFE_MODE=1: the danger word (SAFE_MODE_DANGER_WORDS, comma-separated, default
+/// "delete,remove,void,cancel subscription") found in `label`, if any.
+fn danger_word(label: &str) -> Option<String> {
+    if !safe_mode_enabled() {
+        return None;
+    }
+    let words = env::var("SAFE_MODE_DANGER_WORDS")
+        .unwrap_or_else(|_| "delete,remove,void,cancel subscription".to_string());
+    let label = label.to_lowercase();
+    words
+        .split(',')
+        .map(|w| w.trim().to_lowercase())
+        .find(|w| !w.is_empty() && label.contains(w.as_str()))
+}
+
+/// Guard before clicking a control labelled `label`. Under SAFE_MODE a danger-listed
+/// label needs confirmation (see `require_confirmation`).
+pub async fn confirm_destructive(label: &str) -> Result<()> {
+    let Some(word) = danger_word(label) else {
+        return Ok(());
+    };
+    require_confirmation(&format!("click {label:?} (matches {word:?})")).await
+}
+
+/// Guard before a SAFE_MODE click whose target label could not be read (no element,
+/// an iframe/canvas, a script error, or a display capture): same confirmation as a
+/// danger-listed label, since the target cannot be ruled safe.
+pub async fn confirm_unlabelled_click(at: &str) -> Result<()> {
+    if !safe_mode_enabled() {
+        return Ok(());
+    }
+    require_confirmation(&format!("click {at} (no readable control label there)")).await
+}
+
+/// SAFE_MODE confirmation for `what` ("click ..."): ALLOW_DESTRUCTIVE=1, or (HUMAN_IN_LOOP=1
+/// on a terminal) a typed "yes"; anything else refuses with an error. DRY_RUN only logs,
+/// since nothing is clicked.
+async fn require_confirmation(what: &str) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if dry_run_enabled() {
+        println!("[DRY_RUN] would require SAFE_MODE confirmation to {what}");
+        return Ok(());
+    }
+    if env::var("ALLOW_DESTRUCTIVE").map_or(false, |v| v == "1") {
+        println!("⚠️ SAFE_MODE: allowing {what} because ALLOW_DESTRUCTIVE=1");
+        return Ok(());
+    }
+    let human = env::var("HUMAN_IN_LOOP").map_or(false, |v| v == "1");
+    if human && std::io::stdin().is_terminal() {
+        crate::console::discard_pending_lines().await;
+        println!("🛑 SAFE_MODE: about to {what}. Type \"yes\" to allow: ");
+        let answer = crate::console::operator_pause(crate::console::next_line()).await.unwrap_or_default();
+        if answer.trim().eq_ignore_ascii_case("yes") {
+            return Ok(());
+        }
+        anyhow::bail!("SAFE_MODE: operator declined to {what}");
+    }
+    anyhow::bail!("SAFE_MODE: refusing to {what}; set ALLOW_DESTRUCTIVE=1 or HUMAN_IN_LOOP=1 to confirm")
+}
+
+async fn click_candidate(
+    cands: &[Candidate],
+    outcome: ClickOutcome,
+    force_double: Option<bool>,
+) -> Result<ClickOutcome> {
+    let idx = outcome.chosen_idx;
+    let el = occurrence_element(&cands[idx], outcome.nth);
+    let meta = &cands[idx].meta;
+    confirm_destructive(format!("{} {} {}", meta.text, meta.aria, meta.value).trim()).await?;
+
+    if dry_run_enabled() {
+        println!(
+            "[DRY_RUN] would click: idx={} nth={:?} tag={} text={:?} aria={:?} rect={:?}",
+            idx, outcome.nth, cands[idx].meta.tag, cands[idx].meta.text, cands[idx].meta.aria, cands[idx].rect
+        );
+        return Ok(outcome);
+    }
 
     // Prefer WebDriver click first (more semantically correct)
     // If your site needs a pointer-based click at center, you can compute it from rect().
@@ -771,16 +1498,134 @@ pub async fn click_by_llm_dom_first(
         idx, cands[idx].meta.tag, cands[idx].meta.text, cands[idx].meta.aria
     );
 
-    Ok(())
+    Ok(outcome)
 }
 
 //END OF DOM TESTING
 
+/// `focus_rect` (image pixels) concentrates the grid overlay around a region of interest,
+/// e.g. the previous attempt's point on a retry. None draws the usual full grid.
+///
+/// The aggregate must land inside the screenshot (± POINT_BOUNDS_MARGIN_PX, default 20);
+/// otherwise the sample set is discarded and re-run once, then OutOfBounds is returned
+/// rather than clamping a hallucinated point onto some corner.
 pub async fn call_openai_for_point(
     cfg: &OpenAIConfig,
     screenshot_png: &[u8],
     user_prompt: &str,
-) -> Result<ViewportPoint> {
+    focus_rect: Option<(u32, u32, u32, u32)>,
+) -> Result<ViewportPoint, OpenAiError> {
+    Ok(sample_checked_point(cfg, screenshot_png, user_prompt, focus_rect).await?.0)
+}
+
+/// `call_openai_for_point`, plus whether the point survived LLM_CONFIRM_CLICK (true when
+/// the check is off). A disowned point is still returned if its re-sample went off-screen.
+async fn sample_checked_point(
+    cfg: &OpenAIConfig,
+    screenshot_png: &[u8],
+    user_prompt: &str,
+    focus_rect: Option<(u32, u32, u32, u32)>,
+) -> Result<(ViewportPoint, bool), OpenAiError> {
+    let (w, h) = crate::coords::png_dimensions(screenshot_png).map_err(OpenAiError::Other)?;
+    let margin: i32 = env::var("POINT_BOUNDS_MARGIN_PX")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(20);
+
+    let mut last = None;
+    for round in 1..=2 {
+        let (mut results, mut agg) = sample_points(cfg, screenshot_png, user_prompt, focus_rect).await?;
+        if point_in_bounds(agg, w, h, margin) {
+            let mut confirmed = true;
+            // LLM_CONFIRM_CLICK=1: show the model its own point; one re-sample if it disowns it
+            if confirm_click_enabled() && !confirm_point(cfg, screenshot_png, user_prompt, agg).await? {
+                eprintln!("   🔁 model rejected ({}, {}); re-sampling once", agg.x, agg.y);
+                let (again, again_agg) =
+                    sample_points(cfg, screenshot_png, user_prompt, focus_rect).await?;
+                if point_in_bounds(again_agg, w, h, margin) {
+                    results = again;
+                    agg = again_agg;
+                } else {
+                    eprintln!("   ⚠️ re-sampled point is off-screen; keeping the first one");
+                    confirmed = false;
+                }
+            }
+            if let Err(e) = save_dotmap_png(screenshot_png, &results, agg) {
+                eprintln!("(non-fatal) failed to write dot map: {e}");
+            }
+            if let Err(e) = append_click_ledger(user_prompt, &results, agg) {
+                eprintln!("(non-fatal) failed to append click ledger: {e}");
+            }
+            return Ok((agg, confirmed));
+        }
+        eprintln!(
+            "   ⚠️ aggregate ({}, {}) is outside the {w}x{h} screenshot (round {round}/2); discarding samples",
+            agg.x, agg.y
+        );
+        last = Some(agg);
+    }
+
+    let agg = last.unwrap_or(ViewportPoint { x: -1, y: -1, double: false, confidence: None });
+    Err(OpenAiError::OutOfBounds { x: agg.x, y: agg.y, width: w, height: h })
+}
+
+fn confirm_click_enabled() -> bool {
+    env::var("LLM_CONFIRM_CLICK").map_or(false, |v| v == "1")
+}
+
+/// Draw `pt` on the screenshot and ask whether it lands on the `user_prompt` target.
+async fn confirm_point(
+    cfg: &OpenAIConfig,
+    screenshot_png: &[u8],
+    user_prompt: &str,
+    pt: ViewportPoint,
+) -> Result<bool, OpenAiError> {
+    let marked = (|| -> Result<Vec<u8>> {
+        let mut rgba = image::load_from_memory(screenshot_png)?.to_rgba8();
+        draw_filled_circle(&mut rgba, pt.x, pt.y, 9, Rgba([0, 0, 0, 255]));
+        draw_filled_circle(&mut rgba, pt.x, pt.y, 6, Rgba([255, 0, 255, 255]));
+        let mut out = Vec::new();
+        DynamicImage::ImageRgba8(rgba)
+            .write_to(&mut std::io::Cursor::new(&mut out), ImageOutputFormat::Png)?;
+        Ok(out)
+    })()
+    .map_err(OpenAiError::Other)?;
+
+    let question = format!(
+        "A magenta dot with a black outline has been drawn on this screenshot. Does the dot \
+         land on the element for this instruction: \"{user_prompt}\"? Answer yes or no."
+    );
+    match ask_boolean_question(cfg, &marked, &question).await {
+        Ok(v) => {
+            println!("   ✅ confirm pass: {} (confidence {:?})", v.answer, v.confidence);
+            Ok(v.answer)
+        }
+        Err(e) => match e.downcast::<OpenAiError>() {
+            Ok(budget @ OpenAiError::BudgetExhausted { .. }) => Err(budget),
+            // A failed confirmation shouldn't block the click it was meant to check
+            Ok(other) => {
+                eprintln!("   ⚠️ confirm pass failed ({other}); keeping the point");
+                Ok(true)
+            }
+            Err(e) => {
+                eprintln!("   ⚠️ confirm pass failed ({e:#}); keeping the point");
+                Ok(true)
+            }
+        },
+    }
+}
+
+fn point_in_bounds(pt: ViewportPoint, w: u32, h: u32, margin: i32) -> bool {
+    (-margin..=w as i32 + margin).contains(&pt.x) && (-margin..=h as i32 + margin).contains(&pt.y)
+}
+
+/// One round of concurrent sampling; returns the raw samples and their combined point.
+async fn sample_points(
+    cfg: &OpenAIConfig,
+    screenshot_png: &[u8],
+    user_prompt: &str,
+    focus_rect: Option<(u32, u32, u32, u32)>,
+) -> Result<(Vec<ViewportPoint>, ViewportPoint), OpenAiError> {
     // Check if we should pause due to excessive rate limiting BEFORE spawning concurrent requests
     let should_pause = with_rate_limit_tracker(|tracker| tracker.should_pause());
     if should_pause {
@@ -812,18 +1657,30 @@ pub async fn call_openai_for_point(
         .unwrap_or(120);
 
     println!(
-        "🤖 Sampling OpenAI {} times (IQR-filtered mean combine, concurrency={}, stagger={}ms...",
-        samples, max_conc, stagger_ms
+        "🤖 Sampling OpenAI {} times ({:?} combine, concurrency={}, stagger={}ms...",
+        samples, AggregateStrategy::from_env(), max_conc, stagger_ms
     );
 
+    // SAMPLE_RESUME=1: samples a crashed attempt at this step already paid for
+    let resume_path = partial_samples_path(user_prompt, focus_rect);
+    let mut results: Vec<ViewportPoint> = Vec::with_capacity(samples);
+    if let Some(path) = &resume_path {
+        results = load_partial_samples(path);
+        results.truncate(samples);
+        if !results.is_empty() {
+            println!("   ♻️ resuming with {} saved sample(s) from {}", results.len(), path.display());
+        }
+    }
+    let resumed = results.len();
+
     let mut set = JoinSet::new();
     let cfg_cloned = cfg.clone();
     let img = screenshot_png.to_vec();
     let prompt = user_prompt.to_string();
 
     // spawn initial batch
-    let initial = std::cmp::min(samples, max_conc);
-    for i in 0..initial {
+    let initial = std::cmp::min(samples - resumed, max_conc);
+    for i in resumed..resumed + initial {
         let cfg_i = cfg_cloned.clone();
         let img_i = img.clone();
         let prompt_i = prompt.clone();
@@ -834,13 +1691,12 @@ pub async fn call_openai_for_point(
                 let delay = stagger * ((i as u64 % 8) + 1);
                 tokio::time::sleep(Duration::from_millis(delay)).await;
             }
-            let res = call_openai_once(&cfg_i, &img_i, &prompt_i).await;
+            let res = call_openai_once(&cfg_i, &img_i, &prompt_i, focus_rect, i).await;
             (i, res)
         });
     }
-    let mut launched = initial;
+    let mut launched = resumed + initial;
 
-    let mut results: Vec<ViewportPoint> = Vec::with_capacity(samples);
     let mut rate_limit_failures = 0;
     let mut total_failures = 0;
     
@@ -849,16 +1705,19 @@ pub async fn call_openai_for_point(
             Ok((idx, Ok(pt))) => {
                 println!("   → Sample {}: x={}, y={}, double={}", idx + 1, pt.x, pt.y, pt.double);
                 results.push(pt);
+                if let Some(path) = &resume_path {
+                    if let Err(e) = save_partial_samples(path, &results) {
+                        eprintln!("   (non-fatal) could not save partial samples: {e}");
+                    }
+                }
+            }
+            Ok((_idx, Err(e @ OpenAiError::BudgetExhausted { .. }))) => {
+                // Dropping the set aborts the samples still in flight
+                return Err(e);
             }
             Ok((_idx, Err(e))) => {
                 total_failures += 1;
-                // Check if the error is related to rate limiting
-                // call_openai_once now includes "(rate limited)" in the error message
-                // when it encounters 429 errors, so we can detect it
-                let error_str = e.to_string().to_lowercase();
-                if error_str.contains("429") || error_str.contains("rate") || 
-                   error_str.contains("rate limit") || error_str.contains("ratelimit") ||
-                   error_str.contains("(rate limited)") {
+                if e.is_rate_limit() {
                     rate_limit_failures += 1;
                 }
                 eprintln!("   ⚠️ sample failed: {e}");
@@ -880,7 +1739,7 @@ pub async fn call_openai_for_point(
                     let delay = stagger * ((idx as u64 % 8) + 1);
                     tokio::time::sleep(Duration::from_millis(delay)).await;
                 }
-                let res = call_openai_once(&cfg_i, &img_i, &prompt_i).await;
+                let res = call_openai_once(&cfg_i, &img_i, &prompt_i, focus_rect, idx).await;
                 (idx, res)
             });
             launched += 1;
@@ -911,22 +1770,113 @@ pub async fn call_openai_for_point(
     }
 
     if results.is_empty() {
-        anyhow::bail!("All OpenAI samples failed");
+        return Err(OpenAiError::AllSamplesFailed { samples, rate_limited: rate_limit_failures });
+    }
+
+    // The round completed; a later sampling of this step must not reuse these
+    if let Some(path) = &resume_path {
+        let _ = fs::remove_file(path);
     }
 
     let agg = aggregate_points(&results);
-    if let Err(e) = save_dotmap_png(screenshot_png, &results, agg) {
-        eprintln!("(non-fatal) failed to write dot map: {e}");
+    Ok((results, agg))
+}
+
+#[derive(Serialize, Deserialize)]
+struct PartialSamples {
+    saved_at: u64, // unix seconds
+    samples: Vec<ViewportPoint>,
+}
+
+/// SAMPLE_RESUME=1: sidecar for the samples collected so far by this step's point call,
+/// keyed by the step tag (`steplog::step_tag`) + prompt (+ focus region). It lives in the runs base folder,
+/// since a restarted process gets a new run folder.
+fn partial_samples_path(prompt: &str, focus_rect: Option<(u32, u32, u32, u32)>) -> Option<PathBuf> {
+    if !env::var("SAMPLE_RESUME").map_or(false, |v| v == "1") {
+        return None;
     }
+    let step = crate::steplog::step_tag();
+    let material = format!("{step}\n{prompt}\n{focus_rect:?}");
+    let digest = ring::digest::digest(&ring::digest::SHA256, material.as_bytes());
+    let hash: String = digest.as_ref()[..8].iter().map(|b| format!("{b:02x}")).collect();
+
+    let dir = run_dirs().base.join("partial-samples");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(format!("step-{step}-{hash}.json")))
+}
 
-    Ok(agg)
+/// Saved samples younger than SAMPLE_RESUME_TTL_SECS (default 3600); older ones were
+/// taken against a page that has likely changed.
+fn load_partial_samples(path: &std::path::Path) -> Vec<ViewportPoint> {
+    let ttl_secs: u64 = env::var("SAMPLE_RESUME_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(3600);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<PartialSamples>(&bytes).ok())
+        .filter(|p| now.saturating_sub(p.saved_at) <= ttl_secs)
+        .map(|p| p.samples)
+        .unwrap_or_default()
 }
 
-async fn call_openai_once(
+fn save_partial_samples(path: &std::path::Path, samples: &[ViewportPoint]) -> Result<()> {
+    let saved_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let record = PartialSamples { saved_at, samples: samples.to_vec() };
+    fs::write(path, serde_json::to_vec(&record)?)?;
+    Ok(())
+}
+
+/// Same as `call_openai_for_point`, but consults the on-disk decision cache first
+/// when LLM_CACHE=1. Entries are keyed by prompt + page URL + screenshot size + focus
+/// rect. Focused (retry) passes and SCREENSHOT_SOURCE=display captures (whose content
+/// is not tied to the page URL) bypass the cache; a hit the model disowns under
+/// LLM_CONFIRM_CLICK is evicted and re-sampled, and a disowned fresh point is not stored.
+/// `evict_last_cached_point` drops the entry once a click's validation fails.
+pub async fn call_openai_for_point_cached(
     cfg: &OpenAIConfig,
     screenshot_png: &[u8],
     user_prompt: &str,
+    page_url: &str,
+    focus_rect: Option<(u32, u32, u32, u32)>,
 ) -> Result<ViewportPoint> {
+    let cache_enabled = env::var("LLM_CACHE").map_or(false, |v| v == "1");
+    *last_point_cache_key().lock().unwrap() = None;
+    if !cache_enabled || focus_rect.is_some() || display_screenshot_source() {
+        return Ok(call_openai_for_point(cfg, screenshot_png, user_prompt, focus_rect).await?);
+    }
+
+    let (w, h) = crate::coords::png_dimensions(screenshot_png)?;
+    let key = point_cache_key(user_prompt, page_url, w, h, focus_rect);
+    *last_point_cache_key().lock().unwrap() = Some(key.clone());
+
+    if let Some(pt) = load_cached_point(&key) {
+        if confirm_click_enabled() && !confirm_point(cfg, screenshot_png, user_prompt, pt).await? {
+            println!("💾 LLM cache entry for {:?} at {page_url} rejected by the model; evicting", user_prompt);
+            evict_cached_point(&key);
+        } else {
+            println!("💾 LLM cache hit for {:?} at {page_url}: x={}, y={}", user_prompt, pt.x, pt.y);
+            return Ok(pt);
+        }
+    }
+
+    let (pt, confirmed) = sample_checked_point(cfg, screenshot_png, user_prompt, focus_rect).await?;
+    if confirmed {
+        if let Err(e) = store_cached_point(&key, pt) {
+            eprintln!("(non-fatal) failed to write LLM cache: {e}");
+        }
+    }
+    Ok(pt)
+}
+
+async fn call_openai_once(
+    cfg: &OpenAIConfig,
+    screenshot_png: &[u8],
+    user_prompt: &str,
+    focus_rect: Option<(u32, u32, u32, u32)>,
+    sample_idx: usize,
+) -> Result<ViewportPoint, OpenAiError> {
     // Check if we should pause due to excessive rate limiting
     let should_pause = with_rate_limit_tracker(|tracker| tracker.should_pause());
     if should_pause {
@@ -939,16 +1889,23 @@ async fn call_openai_once(
         with_rate_limit_tracker(|tracker| tracker.record_success());
     }
 
-    let client = reqwest::Client::builder().timeout(cfg.timeout).build()?;
+    // Held until this sample (including its retries) finishes
+    let _permit = global_request_permits()
+        .acquire()
+        .await
+        .map_err(|e| OpenAiError::Other(e.into()))?;
+
+    let client = cfg.http_client().map_err(OpenAiError::Transport)?;
 
     let overlay_enabled = env::var("OPENAI_OVERLAY_GRID")
         .map(|v| v != "0" && v.to_lowercase() != "false")
         .unwrap_or(true);
 
     let annotated_png = if overlay_enabled {
-        let grid_opts = GridOptions::from_env();
+        let grid_opts = GridOptions::from_env().with_focus(focus_rect);
         overlay_grid_with_coords(screenshot_png, grid_opts)
-            .context("overlay grid on screenshot")?
+            .context("overlay grid on screenshot")
+            .map_err(OpenAiError::Other)?
     } else {
         screenshot_png.to_vec()
     };
@@ -956,18 +1913,19 @@ async fn call_openai_once(
     let b64 = base64::engine::general_purpose::STANDARD.encode(&annotated_png);
     let data_url = format!("data:image/png;base64,{}", b64);
     let full_prompt = format!(
-        "{}\nReturn only JSON in the exact form {{\"x\":int,\"y\":int,\"double\":bool}}.",
+        "{}\nReturn only JSON in the exact form {{\"x\":int,\"y\":int,\"double\":bool,\"confidence\":0..1}}.",
         user_prompt
     );
 
     let messages = vec![
         ChatMessage {
             role: "system",
-            content: ChatContent::Text(format!(
+            content: ChatContent::Text(cfg.system_prompt(
                 "You are selecting a single click target on the image. \
-                 Output ONLY JSON (no markdown fences, no prose) with keys x:int,y:int,double:bool. \
+                 Output ONLY JSON (no markdown fences, no prose) with keys x:int,y:int,double:bool,confidence:number 0..1. \
                  Coordinates are CSS/viewport pixels relative to the visible page (top-left). \
-		 Be specific, do not estimate."
+		 Be specific, do not estimate.",
+                POINT_JSON_CONTRACT,
             )),
         },
         ChatMessage {
@@ -986,14 +1944,14 @@ async fn call_openai_once(
         messages,
     };
 
-    let url = format!("{}/chat/completions", cfg.base_url);
-    let mut last_err: Option<anyhow::Error> = None;
-    let mut encountered_429 = false;
+    let mut last_err: Option<OpenAiError> = None;
+    let mut rate_limited = 0;
 
     for attempt in 0..cfg.max_retries {
-        let resp = client
-            .post(&url)
-            .bearer_auth(&cfg.api_key)
+        // Every POST counts against LLM_MAX_REQUESTS, retries included
+        reserve_llm_request()?;
+        let resp = cfg
+            .post_chat(&client)
             .json(&req_body)
             .send()
             .await;
@@ -1008,7 +1966,7 @@ async fn call_openai_once(
 		    let text = r.text().await.unwrap_or_default();
   		    
                     if status.as_u16() == 429 {
-                        encountered_429 = true;
+                        rate_limited += 1;
                         let wait_ms = compute_rate_limit_sleep_ms(&headers, &text, attempt);
                         eprintln!(
                             "⏳ 429 rate-limited (attempt {}/{}). Sleeping ~{} ms",
@@ -1019,7 +1977,7 @@ async fn call_openai_once(
                     }
 
                     // other non-success -> record and try again with small backoff
-                    last_err = Some(anyhow::anyhow!("OpenAI HTTP {}: {}", status, text));
+                    last_err = Some(OpenAiError::Http { status: status.as_u16(), body: text });
                 } else {
                     // Success - reset rate limit tracker
                     with_rate_limit_tracker(|tracker| tracker.record_success());
@@ -1040,7 +1998,7 @@ async fn call_openai_once(
                         .and_then(|v| v.to_str().ok())
                         .and_then(|s| s.parse::<u64>().ok());
 
-                    let parsed: ChatResponse = r.json().await?;
+                    let parsed: ChatResponse = r.json().await.map_err(OpenAiError::Transport)?;
                     
                     // Log token usage and rate limit info from this request
                     if let Some(ref usage) = parsed.usage {
@@ -1065,27 +2023,34 @@ async fn call_openai_once(
                     let content = parsed
                         .choices
                         .get(0)
-                        .ok_or_else(|| anyhow::anyhow!("No choices from OpenAI"))?
+                        .ok_or_else(|| OpenAiError::ParseFailed {
+                            raw: String::new(),
+                            reason: "No choices from OpenAI".into(),
+                        })?
                         .message
                         .content
                         .trim()
                         .to_string();
 
                     let cleaned = strip_code_fences(&content);
+                    if env::var("DEBUG_LLM_RESPONSES").map_or(false, |v| v == "1") {
+                        if let Err(e) = write_llm_debug(sample_idx, user_prompt, cleaned, &annotated_png) {
+                            eprintln!("(non-fatal) failed to write LLM debug record: {e}");
+                        }
+                    }
                     
 		    match serde_json::from_str::<ViewportPoint>(cleaned) {
                         Ok(pt) => return Ok(pt),
 			Err(e) => {
-                            last_err = Some(anyhow::anyhow!(
-                                "Failed to parse JSON from OpenAI: {}\nRaw content: {}",
-                                e,
-                                content
-                            ));
+                            last_err = Some(OpenAiError::ParseFailed {
+                                raw: content,
+                                reason: e.to_string(),
+                            });
                         }
                     }
                 }
             }
-            Err(e) => last_err = Some(anyhow::anyhow!(e)),
+            Err(e) => last_err = Some(OpenAiError::Transport(e)),
         }
 
         if attempt + 1 < cfg.max_retries {
@@ -1097,17 +2062,58 @@ async fn call_openai_once(
     // Note: We don't record failures here because call_openai_once is only called
     // from call_openai_for_point, which handles failure tracking at a higher level
     // to avoid recording multiple failures for concurrent requests
-    // However, we include rate limit info in the error message for better detection
+    // A run of 429s leaves last_err unset; report it as RateLimited so the caller can tell.
+    Err(match last_err {
+        Some(e) => e,
+        None if rate_limited > 0 => OpenAiError::RateLimited { attempts: rate_limited },
+        None => OpenAiError::Other(anyhow::anyhow!("no OpenAI request sent (OPENAI_MAX_RETRIES is 0)")),
+    })
+}
 
-    let error_msg = if encountered_429 {
-        "OpenAI request failed (rate limited)".to_string()
-    } else {
-        "OpenAI request failed".to_string()
-    };
-    Err(last_err.unwrap_or_else(|| anyhow::anyhow!(error_msg)))
+/// How sampled points are combined (OPENAI_AGGREGATE=mean|median|iqr_mean|densest).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateStrategy {
+    Mean,
+    Median,
+    /// Mean after dropping values outside Q1 - 1.5×IQR .. Q3 + 1.5×IQR (default)
+    IqrMean,
+    /// The sample with the most neighbours within OPENAI_DENSEST_RADIUS_PX (default 25)
+    Densest,
+}
+
+impl AggregateStrategy {
+    pub fn from_env() -> Self {
+        match env::var("OPENAI_AGGREGATE").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "mean" => Self::Mean,
+            "median" => Self::Median,
+            "densest" => Self::Densest,
+            _ => Self::IqrMean,
+        }
+    }
 }
 
 fn aggregate_points(points: &[ViewportPoint]) -> ViewportPoint {
+    aggregate_points_with(AggregateStrategy::from_env(), points)
+}
+
+fn aggregate_points_with(strategy: AggregateStrategy, points: &[ViewportPoint]) -> ViewportPoint {
+    fn mean(v: &[i32]) -> i32 {
+        if v.is_empty() {
+            return 0;
+        }
+        let sum: i64 = v.iter().map(|&x| x as i64).sum();
+        (sum / v.len() as i64) as i32
+    }
+
+    fn median(mut v: Vec<i32>) -> i32 {
+        if v.is_empty() {
+            return 0;
+        }
+        v.sort_unstable();
+        let n = v.len();
+        if n % 2 == 1 { v[n / 2] } else { (v[n / 2 - 1] + v[n / 2]) / 2 }
+    }
+
     // Compute IQR-based filtered mean
     fn filtered_mean(mut v: Vec<i32>) -> i32 {
         if v.is_empty() {
@@ -1152,12 +2158,134 @@ fn aggregate_points(points: &[ViewportPoint]) -> ViewportPoint {
     let xs: Vec<i32> = points.iter().map(|p| p.x).collect();
     let ys: Vec<i32> = points.iter().map(|p| p.y).collect();
     let doubles = points.iter().filter(|p| p.double).count();
+    let double = doubles * 2 >= points.len();
 
-    ViewportPoint {
-        x: filtered_mean(xs),
-        y: filtered_mean(ys),
-        double: doubles * 2 >= points.len(),
+    let (x, y) = match strategy {
+        AggregateStrategy::Mean => (mean(&xs), mean(&ys)),
+        AggregateStrategy::Median => (median(xs), median(ys)),
+        AggregateStrategy::IqrMean => {
+            weighted_iqr_mean(points).unwrap_or_else(|| (filtered_mean(xs), filtered_mean(ys)))
+        }
+        AggregateStrategy::Densest => {
+            let radius: i64 = env::var("OPENAI_DENSEST_RADIUS_PX")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(25);
+            let r2 = radius * radius;
+            let neighbours = |p: &ViewportPoint| {
+                points
+                    .iter()
+                    .filter(|q| {
+                        let dx = (p.x - q.x) as i64;
+                        let dy = (p.y - q.y) as i64;
+                        dx * dx + dy * dy <= r2
+                    })
+                    .count()
+            };
+            // Ties keep the earliest sample so the result is deterministic
+            let best = points
+                .iter()
+                .fold(None::<(&ViewportPoint, usize)>, |acc, p| {
+                    let n = neighbours(p);
+                    match acc {
+                        Some((_, best_n)) if best_n >= n => acc,
+                        _ => Some((p, n)),
+                    }
+                })
+                .map(|(p, _)| (p.x, p.y));
+            best.unwrap_or((0, 0))
+        }
+    };
+
+    let confidence = mean_confidence(points);
+    ViewportPoint { x, y, double, confidence }
+}
+
+/// Confidence-weighted mean of the samples inside both axes' 1.5×IQR fences. None unless
+/// every sample reported a confidence (mixing weighted and unweighted samples would
+/// silently favour whichever kind happened to answer).
+fn weighted_iqr_mean(points: &[ViewportPoint]) -> Option<(i32, i32)> {
+    if points.is_empty() || points.iter().any(|p| p.confidence.is_none()) {
+        return None;
     }
+
+    // Same fences as filtered_mean; fewer than 4 samples are all kept
+    fn fences(mut v: Vec<i32>) -> (i32, i32) {
+        let n = v.len();
+        if n < 4 {
+            return (i32::MIN, i32::MAX);
+        }
+        v.sort_unstable();
+        let (q1, q3) = (v[n / 4], v[(3 * n) / 4]);
+        let iqr = q3 - q1;
+        (q1 - iqr * 3 / 2, q3 + iqr * 3 / 2)
+    }
+    let (xlo, xhi) = fences(points.iter().map(|p| p.x).collect());
+    let (ylo, yhi) = fences(points.iter().map(|p| p.y).collect());
+
+    let kept: Vec<&ViewportPoint> = points
+        .iter()
+        .filter(|p| (xlo..=xhi).contains(&p.x) && (ylo..=yhi).contains(&p.y))
+        .collect();
+    // A zero-confidence sample still counts a little, so all-zero sets don't divide by 0
+    let weight = |p: &ViewportPoint| f64::from(p.confidence.unwrap_or(0.0).clamp(0.05, 1.0));
+    let total: f64 = kept.iter().map(|p| weight(p)).sum();
+    if kept.is_empty() || total <= 0.0 {
+        return None;
+    }
+    let x = kept.iter().map(|p| p.x as f64 * weight(p)).sum::<f64>() / total;
+    let y = kept.iter().map(|p| p.y as f64 * weight(p)).sum::<f64>() / total;
+    Some((x.round() as i32, y.round() as i32))
+}
+
+/// Mean of the reported sample confidences, if any sample reported one.
+fn mean_confidence(points: &[ViewportPoint]) -> Option<f32> {
+    let reported: Vec<f32> = points.iter().filter_map(|p| p.confidence).collect();
+    if reported.is_empty() {
+        return None;
+    }
+    Some(reported.iter().sum::<f32>() / reported.len() as f32)
+}
+
+/// Population standard deviation of the sample xs and ys.
+fn sample_spread(points: &[ViewportPoint]) -> (f64, f64) {
+    if points.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.x as f64).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.y as f64).sum::<f64>() / n;
+    let var_x = points.iter().map(|p| (p.x as f64 - mean_x).powi(2)).sum::<f64>() / n;
+    let var_y = points.iter().map(|p| (p.y as f64 - mean_y).powi(2)).sum::<f64>() / n;
+    (var_x.sqrt(), var_y.sqrt())
+}
+
+/// Append one row per aggregated click to RUN_DIR/clicks.csv (header written on create).
+fn append_click_ledger(
+    prompt: &str,
+    samples: &[ViewportPoint],
+    aggregate: ViewportPoint,
+) -> Result<()> {
+    use std::io::Write;
+
+    let path = artifact_path("clicks.csv");
+    let is_new = !path.exists();
+    let mut f = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    if is_new {
+        writeln!(f, "timestamp_ms,prompt,samples,agg_x,agg_y,std_x,std_y")?;
+    }
+
+    let ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let (std_x, std_y) = sample_spread(samples);
+    let prompt_csv = format!("\"{}\"", prompt.replace('"', "\"\"").replace('\n', " "));
+    writeln!(
+        f,
+        "{ms},{prompt_csv},{},{},{},{std_x:.2},{std_y:.2}",
+        samples.len(),
+        aggregate.x,
+        aggregate.y
+    )?;
+    Ok(())
 }
 
 fn strip_code_fences(s: &str) -> &str {
@@ -1187,7 +2315,8 @@ fn parse_seconds_str_to_ms(s: &str) -> Option<u64> {
     None
 }
 
-fn extract_wait_ms_from_headers(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+/// Also the Sheets client's Retry-After handling.
+pub(crate) fn extract_wait_ms_from_headers(headers: &reqwest::header::HeaderMap) -> Option<u64> {
     // Prefer explicit reset headers if present
     for key in [
         "x-ratelimit-reset-requests",
@@ -1236,10 +2365,117 @@ fn compute_rate_limit_sleep_ms(
     (base * (attempt as u64 + 1)).min(8_000) // cap at 8s
 }
 
+/* -------------------- Point decision cache -------------------- */
+
+#[derive(Serialize, Deserialize)]
+struct CachedPoint {
+    point: ViewportPoint,
+    saved_at: u64, // unix seconds
+}
+
+fn point_cache_key(
+    prompt: &str,
+    page_url: &str,
+    w: u32,
+    h: u32,
+    focus_rect: Option<(u32, u32, u32, u32)>,
+) -> String {
+    let mut material = format!("{prompt}\n{page_url}\n{w}x{h}");
+    if let Some((x, y, fw, fh)) = focus_rect {
+        material.push_str(&format!("\nfocus {x},{y} {fw}x{fh}"));
+    }
+    let digest = ring::digest::digest(&ring::digest::SHA256, material.as_bytes());
+    digest.as_ref().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The cache lives next to the run folders so it survives across runs.
+fn point_cache_path() -> PathBuf {
+    let base = &run_dirs().base;
+    let _ = fs::create_dir_all(base);
+    base.join("llm-cache.json")
+}
+
+fn read_point_cache() -> HashMap<String, CachedPoint> {
+    fs::read(point_cache_path())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn load_cached_point(key: &str) -> Option<ViewportPoint> {
+    let ttl_secs: u64 = env::var("LLM_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(86_400);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+    let entry = read_point_cache().remove(key)?;
+    if now.saturating_sub(entry.saved_at) > ttl_secs {
+        return None;
+    }
+    Some(entry.point)
+}
+
+/// Key of the last point `call_openai_for_point_cached` looked up or stored.
+fn last_point_cache_key() -> &'static Mutex<Option<String>> {
+    static LAST: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+    LAST.get_or_init(|| Mutex::new(None))
+}
+
+fn evict_cached_point(key: &str) {
+    let mut cache = read_point_cache();
+    if cache.remove(key).is_none() {
+        return;
+    }
+    let written = serde_json::to_vec_pretty(&cache)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| fs::write(point_cache_path(), bytes).map_err(Into::into));
+    if let Err(e) = written {
+        eprintln!("(non-fatal) failed to update LLM cache: {e}");
+    }
+}
+
+/// Drop the cached point behind the last cached lookup, e.g. after its click failed
+/// validation, so later clicks ask the model again instead of replaying the miss.
+pub fn evict_last_cached_point() {
+    if let Some(key) = last_point_cache_key().lock().unwrap().take() {
+        evict_cached_point(&key);
+    }
+}
+
+fn store_cached_point(key: &str, point: ViewportPoint) -> Result<()> {
+    let saved_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut cache = read_point_cache();
+    cache.insert(key.to_string(), CachedPoint { point, saved_at });
+    fs::write(point_cache_path(), serde_json::to_vec_pretty(&cache)?)?;
+    Ok(())
+}
+
 /* -------------------- Heat dotmap helpers (time-based) -------------------- */
 
-/// Get the largest numbered run directory without creating a new one.
-/// Returns the directory if found, or None if no run directories exist.
+/// Base folder that run folders are created under: RUNS_BASE_DIR, default "runs".
+pub fn runs_base_dir() -> PathBuf {
+    PathBuf::from(std::env::var("RUNS_BASE_DIR").unwrap_or_else(|_| "runs".to_string()))
+}
+
+/// Is `name` a run folder this crate creates: `run-NNN`, or `<YYYY-MM-DD>_<label>` (RUN_LABEL,
+/// possibly suffixed `-2`, `-3`, ...)?
+fn is_run_folder_name(name: &str) -> bool {
+    if let Some(num) = name.strip_prefix("run-") {
+        return !num.is_empty() && num.bytes().all(|b| b.is_ascii_digit());
+    }
+    let Some((date, label)) = name.split_once('_') else {
+        return false;
+    };
+    let b = date.as_bytes();
+    b.len() == 10
+        && b.iter().enumerate().all(|(i, c)| if i == 4 || i == 7 { *c == b'-' } else { c.is_ascii_digit() })
+        && !label.is_empty()
+}
+
+/// Get the most recent run directory without creating a new one: RUN_DIR if it exists,
+/// else the newest (by modification time) `run-NNN` or dated/labelled run folder.
+/// Returns None if no run directories exist.
 pub fn get_largest_run_dir() -> Option<PathBuf> {
     // Check if RUN_DIR is already set (current run directory)
     if let Ok(dir) = std::env::var("RUN_DIR") {
@@ -1248,56 +2484,67 @@ pub fn get_largest_run_dir() -> Option<PathBuf> {
             return Some(path);
         }
     }
-    
-    // Otherwise, find the largest numbered run directory
-    let base_dir = PathBuf::from("runs");
-    if !base_dir.exists() {
-        return None;
-    }
-    
-    let mut max_run_num = 0;
-    let mut max_run_dir: Option<PathBuf> = None;
-    
-    // Read the base directory and find the highest run number
-    if let Ok(entries) = fs::read_dir(&base_dir) {
-        for entry in entries.flatten() {
-            if let Some(name) = entry.file_name().to_str() {
-                if name.starts_with("run-") {
-                    // Try to parse the number after "run-"
-                    if let Some(num_str) = name.strip_prefix("run-") {
-                        if let Ok(num) = num_str.parse::<usize>() {
-                            if num > max_run_num {
-                                max_run_num = num;
-                                max_run_dir = Some(entry.path());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    max_run_dir
+
+    let entries = fs::read_dir(runs_base_dir()).ok()?;
+    entries
+        .flatten()
+        .filter(|e| e.path().is_dir() && e.file_name().to_str().map_or(false, is_run_folder_name))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
 }
 
-fn ensure_run_dir() -> PathBuf {
-    let base_dir = if let Ok(dir) = std::env::var("RUN_DIR") {
-        PathBuf::from(dir)
-    } else {
-        PathBuf::from("runs")
+/// This process's run folder and the base folder it lives in, resolved once.
+struct RunDirs {
+    base: PathBuf,
+    run: PathBuf,
+}
+
+static RUN_DIRS: OnceLock<RunDirs> = OnceLock::new();
+
+fn run_dirs() -> &'static RunDirs {
+    RUN_DIRS.get_or_init(|| {
+        let dirs = resolve_run_dirs();
+        std::env::set_var("RUN_DIR", &dirs.run);
+        dirs
+    })
+}
+
+fn resolve_run_dirs() -> RunDirs {
+    let base_dir = match std::env::var("RUN_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => runs_base_dir(),
     };
     
-    // Check if the path is already a specific run folder (contains "run-")
-    let path_str = base_dir.to_string_lossy();
-    if path_str.contains("run-") && base_dir.exists() && base_dir.is_dir() {
+    // Check if the path is already a specific run folder (run-NNN or <date>_<label>)
+    let is_run_folder = base_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map_or(false, is_run_folder_name);
+    if is_run_folder && base_dir.exists() && base_dir.is_dir() {
         // It's already a specific run folder, use it as-is
-        let _ = fs::create_dir_all(&base_dir);
-        return base_dir;
+        let base = base_dir.parent().map(PathBuf::from).unwrap_or_else(|| base_dir.clone());
+        return RunDirs { base, run: base_dir };
     }
     
-    // It's a base directory, find or create the next sequential run folder
+    // It's a base directory, find or create the next run folder
     let _ = fs::create_dir_all(&base_dir);
     
+    // RUN_LABEL=nightly names the folder <UTC date>_nightly, suffixed -2, -3, ... if taken
+    if let Some(label) = std::env::var("RUN_LABEL").ok().filter(|l| !l.trim().is_empty()) {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let stem = format!("{}_{}", utc_date(secs), sanitize_label(&label));
+        let mut p = base_dir.join(&stem);
+        for i in 2.. {
+            if !p.exists() {
+                break;
+            }
+            p = base_dir.join(format!("{stem}-{i}"));
+        }
+        let _ = fs::create_dir_all(&p);
+        return RunDirs { base: base_dir, run: p };
+    }
+    
     let mut max_run_num = 0;
     
     // Read the base directory and find the highest run number
@@ -1320,20 +2567,84 @@ fn ensure_run_dir() -> PathBuf {
     let next_run_num = max_run_num + 1;
     let p = base_dir.join(format!("run-{:03}", next_run_num));
     let _ = fs::create_dir_all(&p);
-    std::env::set_var("RUN_DIR", &p);
-    p
+    RunDirs { base: base_dir, run: p }
 }
 
-fn dotmap_path_timebased() -> PathBuf {
-    let run_dir = ensure_run_dir();
-    if let Ok(step_str) = std::env::var("CURRENT_STEP_NO") {
-        if let Ok(step_no) = step_str.parse::<usize>() {
-            let ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
-            return run_dir.join(format!("step-{:02}-llm-dots-{}.png", step_no, ms));
+/// Keep labels path-safe: anything but letters, digits, '-' and '_' becomes '-'.
+fn sanitize_label(label: &str) -> String {
+    label
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+/// YYYY-MM-DD (UTC) for a unix timestamp (days-to-civil, proleptic Gregorian).
+fn utc_date(unix_secs: u64) -> String {
+    let z = (unix_secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Current run folder (RUNS_BASE_DIR/run-NNN, or RUNS_BASE_DIR/<date>_<RUN_LABEL>),
+/// created on first use and exported as RUN_DIR.
+pub fn ensure_run_dir() -> PathBuf {
+    let run = &run_dirs().run;
+    let _ = fs::create_dir_all(run);
+    run.clone()
+}
+
+/// Where every run artifact (screenshots, dotmaps, debug dumps, reports) is written:
+/// `file` inside the current run folder.
+pub fn artifact_path(file: &str) -> PathBuf {
+    ensure_run_dir().join(file)
+}
+
+/// DEBUG_LLM_RESPONSES=1: keep what the model saw and said for one point sample.
+/// Writes RUN_DIR/step-NN-sample-K.json (+ the annotated .png it references); a numeric
+/// suffix is added when the same step asks more than once (retries).
+fn write_llm_debug(sample_idx: usize, prompt: &str, content: &str, annotated_png: &[u8]) -> Result<()> {
+    let step = crate::steplog::step_tag();
+    let base = format!("step-{}-sample-{}", step, sample_idx + 1);
+
+    let mut stem = base.clone();
+    for i in 1.. {
+        if !artifact_path(&format!("{stem}.json")).exists() {
+            break;
         }
+        stem = format!("{base}-{i}");
+    }
+
+    let png_path = artifact_path(&format!("{stem}.png"));
+    fs::write(&png_path, annotated_png)?;
+
+    let parsed: serde_json::Value =
+        serde_json::from_str(content).unwrap_or_else(|_| serde_json::Value::String(content.to_string()));
+    let record = serde_json::json!({
+        "step": step,
+        "sample": sample_idx + 1,
+        "prompt": prompt,
+        "response": parsed,
+        "screenshot": png_path.to_string_lossy(),
+    });
+    fs::write(artifact_path(&format!("{stem}.json")), serde_json::to_vec_pretty(&record)?)?;
+    Ok(())
+}
+
+fn dotmap_path_timebased() -> PathBuf {
+    if std::env::var("CURRENT_STEP_NO").is_ok() {
+        let ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        return artifact_path(&format!("step-{}-llm-dots-{}.png", crate::steplog::step_tag(), ms));
     }
     let ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
-    run_dir.join(format!("llm-dots-{}.png", ms))
+    artifact_path(&format!("llm-dots-{}.png", ms))
 }
 
 fn draw_filled_circle(img: &mut RgbaImage, cx: i32, cy: i32, radius: i32, color: Rgba<u8>) {
@@ -1405,6 +2716,84 @@ fn save_dotmap_png(
         .write_to(&mut std::io::Cursor::new(&mut out), ImageOutputFormat::Png)?;
     fs::write(&path, &out)?;
     println!("🟡 Saved LLM dotmap to {}", path.display());
+
+    // Raw samples next to the image so `--replay` can re-aggregate them offline
+    let sidecar = DotmapSamples {
+        width: w,
+        height: h,
+        strategy: format!("{:?}", AggregateStrategy::from_env()),
+        samples: samples.to_vec(),
+        aggregate,
+    };
+    fs::write(path.with_extension("json"), serde_json::to_vec_pretty(&sidecar)?)?;
+    Ok(())
+}
+
+/// `llm-dots-*.json` sidecar written with each dotmap.
+#[derive(Serialize, Deserialize, Debug)]
+struct DotmapSamples {
+    width: u32,
+    height: u32,
+    /// Strategy that produced `aggregate` at capture time
+    strategy: String,
+    samples: Vec<ViewportPoint>,
+    aggregate: ViewportPoint,
+}
+
+/// `--replay <dir>`: re-run every aggregation strategy over the dotmap sidecars in `dir`
+/// and print them next to the recorded aggregate. Makes no OpenAI calls.
+pub fn replay_dotmaps(dir: &std::path::Path) -> Result<()> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("cannot read {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension().map_or(false, |x| x == "json")
+                && p.file_name().map_or(false, |n| n.to_string_lossy().contains("llm-dots-"))
+        })
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        anyhow::bail!("no llm-dots-*.json sidecars in {}", dir.display());
+    }
+
+    let strategies = [
+        AggregateStrategy::IqrMean,
+        AggregateStrategy::Mean,
+        AggregateStrategy::Median,
+        AggregateStrategy::Densest,
+    ];
+    for path in files {
+        let rec: DotmapSamples = match fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|b| Ok(serde_json::from_slice(&b)?))
+        {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("⚠️ skipping {}: {e}", path.display());
+                continue;
+            }
+        };
+        println!(
+            "📍 {} ({} samples, {}x{}) recorded {} → ({}, {})",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            rec.samples.len(),
+            rec.width,
+            rec.height,
+            rec.strategy,
+            rec.aggregate.x,
+            rec.aggregate.y
+        );
+        if rec.samples.is_empty() {
+            continue;
+        }
+        for strategy in strategies {
+            let pt = aggregate_points_with(strategy, &rec.samples);
+            let dx = pt.x - rec.aggregate.x;
+            let dy = pt.y - rec.aggregate.y;
+            let name = format!("{strategy:?}");
+            println!("   {name:<8} → ({}, {})  Δ ({dx:+}, {dy:+})", pt.x, pt.y);
+        }
+    }
     Ok(())
 }
 
@@ -1433,7 +2822,7 @@ pub async fn ask_boolean_question(
         with_rate_limit_tracker(|tracker| tracker.record_success());
     }
 
-    let client = reqwest::Client::builder().timeout(cfg.timeout).build()?;
+    let client = cfg.http_client()?;
     
     let b64 = base64::engine::general_purpose::STANDARD.encode(screenshot_png);
     let data_url = format!("data:image/png;base64,{}", b64);
@@ -1469,14 +2858,14 @@ pub async fn ask_boolean_question(
         messages,
     };
     
-    let url = format!("{}/chat/completions", cfg.base_url);
     let mut last_err: Option<anyhow::Error> = None;
     let mut rate_limited = false;
     
     for attempt in 0..cfg.max_retries {
-        let resp = client
-            .post(&url)
-            .bearer_auth(&cfg.api_key)
+        // Every POST counts against LLM_MAX_REQUESTS, retries included
+        reserve_llm_request()?;
+        let resp = cfg
+            .post_chat(&client)
             .json(&req_body)
             .send()
             .await;
@@ -1554,3 +2943,84 @@ pub async fn ask_boolean_question(
     Err(last_err.unwrap_or_else(|| anyhow::anyhow!("OpenAI boolean question request failed")))
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pt(x: i32, y: i32) -> ViewportPoint {
+        ViewportPoint { x, y, double: false, confidence: None }
+    }
+
+    #[test]
+    fn far_off_screen_point_is_rejected() {
+        assert!(!point_in_bounds(pt(99999, -5), 1280, 800, 20));
+    }
+
+    #[test]
+    fn points_on_the_margin_edge_are_accepted() {
+        assert!(point_in_bounds(pt(-20, -20), 1280, 800, 20));
+        assert!(point_in_bounds(pt(1300, 820), 1280, 800, 20));
+        assert!(!point_in_bounds(pt(-21, 400), 1280, 800, 20));
+        assert!(!point_in_bounds(pt(640, 821), 1280, 800, 20));
+    }
+
+    #[test]
+    fn point_inside_the_screenshot_is_accepted() {
+        assert!(point_in_bounds(pt(640, 400), 1280, 800, 20));
+        assert!(point_in_bounds(pt(0, 0), 1280, 800, 0));
+    }
+
+    #[test]
+    fn run_folder_names_cover_both_schemes() {
+        assert!(is_run_folder_name("run-001"));
+        assert!(is_run_folder_name("2026-10-14_nightly"));
+        assert!(is_run_folder_name("2026-10-14_nightly-2"));
+        assert!(!is_run_folder_name("runs"));
+        assert!(!is_run_folder_name("run-"));
+        assert!(!is_run_folder_name("my-run-notes"));
+        assert!(!is_run_folder_name("2026-10-14_"));
+    }
+
+    fn xy(p: ViewportPoint) -> (i32, i32) {
+        (p.x, p.y)
+    }
+
+    #[test]
+    fn strategies_agree_on_a_clustered_sample_set() {
+        let points = [pt(98, 100), pt(100, 102), pt(102, 98), pt(100, 100), pt(101, 99)];
+        for strategy in [
+            AggregateStrategy::Mean,
+            AggregateStrategy::Median,
+            AggregateStrategy::IqrMean,
+            AggregateStrategy::Densest,
+        ] {
+            let (x, y) = xy(aggregate_points_with(strategy, &points));
+            assert!((x - 100).abs() <= 2 && (y - 100).abs() <= 2, "{strategy:?} gave ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn strategies_differ_on_a_bimodal_sample_set() {
+        // Three samples on one control, two on another 400px away
+        let points = [pt(100, 100), pt(102, 100), pt(98, 100), pt(500, 100), pt(502, 100)];
+        // Averaging lands between the controls, on neither
+        assert_eq!(xy(aggregate_points_with(AggregateStrategy::Mean, &points)), (260, 100));
+        assert_eq!(xy(aggregate_points_with(AggregateStrategy::IqrMean, &points)), (260, 100));
+        // Median and densest stay on the majority cluster
+        assert_eq!(xy(aggregate_points_with(AggregateStrategy::Median, &points)), (102, 100));
+        assert_eq!(xy(aggregate_points_with(AggregateStrategy::Densest, &points)), (100, 100));
+    }
+
+    #[test]
+    fn densest_tie_keeps_the_earliest_cluster() {
+        let points = [pt(500, 100), pt(502, 100), pt(100, 100), pt(102, 100)];
+        assert_eq!(xy(aggregate_points_with(AggregateStrategy::Densest, &points)), (500, 100));
+    }
+    #[test]
+    fn focus_rect_is_part_of_the_cache_key() {
+        let plain = point_cache_key("Click Save", "https://x/", 1280, 800, None);
+        assert_eq!(plain, point_cache_key("Click Save", "https://x/", 1280, 800, None));
+        assert_ne!(plain, point_cache_key("Click Save", "https://x/", 1280, 800, Some((10, 10, 200, 200))));
+    }
+}
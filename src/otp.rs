@@ -0,0 +1,117 @@
+// src/otp.rs
+//
+// OTP sourcing for Step::TypeOTP. OTP_PROVIDER=keeper (default) reads the code
+// from a Keeper record; OTP_PROVIDER=totp computes it locally from a base32
+// secret in env (TOTP_SECRET_<UID> or TOTP_SECRET).
+
+use anyhow::{bail, Context, Result};
+use ring::hmac;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::plan::fetch_keeper_creds_sync;
+
+// Only used through concrete types / OtpSource, so no Send bound is needed.
+#[allow(async_fn_in_trait)]
+pub trait OtpProvider {
+    /// Ok(None) means the record exists but has no OTP configured.
+    async fn fetch_otp(&self, uid: &str) -> Result<Option<String>>;
+}
+
+pub struct KeeperOtp;
+
+impl OtpProvider for KeeperOtp {
+    async fn fetch_otp(&self, uid: &str) -> Result<Option<String>> {
+        // Keeper SDK is blocking; keep it off the runtime threads.
+        let uid = uid.to_string();
+        let creds = tokio::task::spawn_blocking(move || fetch_keeper_creds_sync(&uid))
+            .await
+            .context("Keeper OTP task panicked")??;
+        Ok(creds.otp)
+    }
+}
+
+pub struct TotpOtp {
+    pub digits: u32,
+    pub period_secs: u64,
+}
+
+impl Default for TotpOtp {
+    fn default() -> Self {
+        Self { digits: 6, period_secs: 30 }
+    }
+}
+
+impl OtpProvider for TotpOtp {
+    async fn fetch_otp(&self, uid: &str) -> Result<Option<String>> {
+        let key = format!("TOTP_SECRET_{}", uid.to_ascii_uppercase().replace('-', "_"));
+        let secret = match std::env::var(&key).or_else(|_| std::env::var("TOTP_SECRET")) {
+            Ok(s) if !s.trim().is_empty() => s,
+            _ => return Ok(None),
+        };
+        let secret = base32_decode(&secret).with_context(|| format!("Invalid base32 secret in {key}"))?;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        Ok(Some(totp_code(&secret, now / self.period_secs, self.digits)))
+    }
+}
+
+/// Which provider Step::TypeOTP should use (OTP_PROVIDER=keeper|totp).
+pub enum OtpSource {
+    Keeper(KeeperOtp),
+    Totp(TotpOtp),
+}
+
+impl OtpSource {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("OTP_PROVIDER").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "" | "keeper" => Ok(Self::Keeper(KeeperOtp)),
+            "totp" => Ok(Self::Totp(TotpOtp::default())),
+            other => bail!("Unknown OTP_PROVIDER '{other}' (expected keeper|totp)"),
+        }
+    }
+
+    pub async fn fetch_otp(&self, uid: &str) -> Result<Option<String>> {
+        match self {
+            Self::Keeper(p) => p.fetch_otp(uid).await,
+            Self::Totp(p) => p.fetch_otp(uid).await,
+        }
+    }
+}
+
+/// RFC 6238 / RFC 4226 code for `counter` (HMAC-SHA1, dynamic truncation).
+fn totp_code(secret: &[u8], counter: u64, digits: u32) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let tag = hmac::sign(&key, &counter.to_be_bytes());
+    let h = tag.as_ref();
+
+    let offset = (h[h.len() - 1] & 0x0f) as usize;
+    let bin = u32::from_be_bytes([h[offset] & 0x7f, h[offset + 1], h[offset + 2], h[offset + 3]]);
+    let code = bin % 10u32.pow(digits);
+    format!("{code:0width$}", width = digits as usize)
+}
+
+/// RFC 4648 base32 (case-insensitive, spaces and '=' padding ignored).
+fn base32_decode(s: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buf: u32 = 0;
+    let mut bits = 0u32;
+
+    for c in s.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let v = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            other => bail!("invalid base32 character {other:?}"),
+        };
+        buf = (buf << 5) | v;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+    if out.is_empty() {
+        bail!("empty secret");
+    }
+    Ok(out)
+}
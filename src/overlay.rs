@@ -1,7 +1,7 @@
 // src/overlay.rs
 use anyhow::{Context, Result};
-use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
-use imageproc::drawing::draw_line_segment_mut;
+use image::{DynamicImage, ImageOutputFormat, Pixel, Rgba, RgbaImage};
+use imageproc::drawing::{draw_line_segment_mut, Blend};
  
 /// Config for the grid overlay.
 #[derive(Debug, Clone, Copy)]
@@ -14,6 +14,19 @@ pub struct GridOptions {
     pub font_scale: u32,
     /// If true, write a debug copy to disk as screenshot_grid.png
     pub save_debug: bool,
+    /// Grid line RGB (GRID_LINE_COLOR, hex like "FF0000")
+    pub line_color: [u8; 3],
+    /// Coordinate label RGB (GRID_LABEL_COLOR, hex like "FFFF00")
+    pub label_color: [u8; 3],
+    /// Opacity for lines and labels, 0..=255 (GRID_ALPHA); blended over the screenshot
+    pub alpha: u8,
+    /// Optional region of interest (x, y, w, h) in image pixels. When set, the grid is
+    /// drawn at half `step` inside the rect and at double `step` everywhere else.
+    pub focus_rect: Option<(u32, u32, u32, u32)>,
+    /// GRID_ADAPTIVE=1: ignore `step` and derive it from the image so the longer side gets
+    /// about `divisions` lines (GRID_DIVISIONS, default 20)
+    pub adaptive: bool,
+    pub divisions: u32,
 }
  
 impl GridOptions {
@@ -22,74 +35,287 @@ impl GridOptions {
         let label_every = std::env::var("GRID_LABEL_EVERY").ok().and_then(|s| s.parse().ok()).unwrap_or(2);
         let font_scale = std::env::var("GRID_FONT_SCALE").ok().and_then(|s| s.parse().ok()).unwrap_or(2);
         let save_debug = std::env::var("GRID_SAVE_DEBUG").map_or(false, |v| v == "1");
-        Self { step, label_every, font_scale, save_debug }
+        let line_color = std::env::var("GRID_LINE_COLOR").ok().and_then(|s| parse_hex_rgb(&s)).unwrap_or([255, 0, 0]);
+        let label_color = std::env::var("GRID_LABEL_COLOR").ok().and_then(|s| parse_hex_rgb(&s)).unwrap_or([255, 0, 0]);
+        let alpha = std::env::var("GRID_ALPHA").ok().and_then(|s| s.parse().ok()).unwrap_or(255);
+        let adaptive = std::env::var("GRID_ADAPTIVE").map_or(false, |v| v == "1");
+        let divisions = std::env::var("GRID_DIVISIONS").ok().and_then(|s| s.parse().ok()).unwrap_or(20);
+        Self {
+            step,
+            label_every,
+            font_scale,
+            save_debug,
+            line_color,
+            label_color,
+            alpha,
+            focus_rect: None,
+            adaptive,
+            divisions,
+        }
+    }
+
+    /// Resolve the adaptive step for a `w`×`h` image (no-op unless `adaptive`).
+    /// The step is rounded to a multiple of 10 so labels stay easy to read, and labels are
+    /// thinned so roughly 10 appear along the longer side whatever the division count.
+    pub fn for_image(mut self, w: u32, h: u32) -> Self {
+        if !self.adaptive {
+            return self;
+        }
+        let divisions = self.divisions.max(1);
+        let raw = w.max(h) / divisions;
+        self.step = ((raw + 5) / 10 * 10).max(10);
+        self.label_every = divisions.div_ceil(10).max(1);
+        self
+    }
+
+    pub fn with_focus(mut self, focus_rect: Option<(u32, u32, u32, u32)>) -> Self {
+        self.focus_rect = focus_rect;
+        self
     }
 }
  
-/// Overlay a green grid + yellow coordinate labels directly on the PNG bytes.
+/// Parse "RRGGBB" (optionally prefixed with '#') into RGB.
+fn parse_hex_rgb(s: &str) -> Option<[u8; 3]> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+    Some([r, g, b])
+}
+ 
+/// Overlay a grid (GRID_LINE_COLOR) and coordinate labels (GRID_LABEL_COLOR), both red
+/// by default, directly on the PNG bytes.
 /// Returns new PNG bytes.
 pub fn overlay_grid_with_coords(png_bytes: &[u8], opts: GridOptions) -> Result<Vec<u8>> {
     // Decode
     let img = image::load_from_memory(png_bytes).context("decode PNG")?;
-    let mut rgba: RgbaImage = img.to_rgba8();
-    let (w, h) = rgba.dimensions();
+    let mut rgba = Blend(img.to_rgba8()); // alpha-blend instead of overwriting pixels
+    let (w, h) = rgba.0.dimensions();
+    let opts = opts.for_image(w, h);
  
-    let grid = Rgba([255, 0, 0, 255]);   // green lines
-    let text = Rgba([255, 0, 0, 255]); // yellow text
+    let [lr, lg, lb] = opts.line_color;
+    let [tr, tg, tb] = opts.label_color;
+    let grid = Rgba([lr, lg, lb, opts.alpha]); // grid lines
+    let text = Rgba([tr, tg, tb, opts.alpha]); // coordinate labels
     let pad = 2 * opts.font_scale;       // small padding for labels
+
+    // Clamp the ROI to the image; an empty/off-image rect falls back to the full grid.
+    let focus = opts.focus_rect.and_then(|(fx, fy, fw, fh)| {
+        let x0 = fx.min(w);
+        let y0 = fy.min(h);
+        let x1 = fx.saturating_add(fw).min(w);
+        let y1 = fy.saturating_add(fh).min(h);
+        (x1 > x0 && y1 > y0).then_some((x0, y0, x1, y1))
+    });
+
+    match focus {
+        None => draw_grid_region(&mut rgba, (0, 0, w, h), opts.step, opts, grid, text, pad),
+        Some(roi) => {
+            // Sparse context grid over the whole image, dense grid (labelled at the ROI edges) inside.
+            let sparse = opts.step.saturating_mul(2).max(1);
+            let dense = (opts.step / 2).max(5);
+            draw_grid_region(&mut rgba, (0, 0, w, h), sparse, opts, grid, text, pad);
+            draw_grid_region(&mut rgba, roi, dense, opts, grid, text, pad);
+        }
+    }
+
+    // Encode back to PNG
+    let mut out = Vec::new();
+    DynamicImage::ImageRgba8(rgba.0)
+        .write_to(&mut std::io::Cursor::new(&mut out), ImageOutputFormat::Png)
+        .context("encode annotated PNG")?;
  
+    if opts.save_debug {
+        let _ = std::fs::write("screenshot_grid.png", &out);
+    }
+    Ok(out)
+}
+ 
+/// Draw grid lines every `step` pixels (multiples of `step` in absolute image coordinates)
+/// within `region` = (x0, y0, x1, y1), with labels along the region's top and left edges.
+#[allow(clippy::too_many_arguments)]
+fn draw_grid_region(
+    rgba: &mut Blend<RgbaImage>,
+    region: (u32, u32, u32, u32),
+    step: u32,
+    opts: GridOptions,
+    grid: Rgba<u8>,
+    text: Rgba<u8>,
+    pad: u32,
+) {
+    let (w, h) = rgba.0.dimensions();
+    let (x0, y0, x1, y1) = region;
+    let step = step.max(1);
+    let first = |lo: u32| lo.div_ceil(step) * step;
+
     // Draw vertical lines and x-labels
-    let mut x_tick = 0u32;
-    while x_tick <= w {
+    let mut x_tick = first(x0);
+    while x_tick <= x1 {
         let x = x_tick.min(w.saturating_sub(1)) as f32;
-        draw_line_segment_mut(&mut rgba, (x, 0.0), (x, h as f32), grid);
- 
-        if opts.label_every > 0 && ((x_tick / opts.step) % opts.label_every == 0) {
-            // Label "x=<num>" near the top of the image at (x+pad, pad)
+        draw_line_segment_mut(rgba, (x, y0 as f32), (x, y1 as f32), grid);
+
+        if opts.label_every > 0 && (x_tick / step).is_multiple_of(opts.label_every) {
+            // Label "<num>" near the top of the region at (x+pad, y0+pad)
             let label = format!("{}", x_tick);
             let lx = x_tick.saturating_add(pad).min(w.saturating_sub(1));
-            let ly = pad.min(h.saturating_sub(1));
-            draw_text_bitmap(&mut rgba, lx as i32, ly as i32, &label, text, opts.font_scale);
+            let ly = y0.saturating_add(pad).min(h.saturating_sub(1));
+            draw_text_bitmap(&mut rgba.0, lx as i32, ly as i32, &label, text, opts.font_scale);
         }
- 
-        match x_tick.checked_add(opts.step) {
+
+        match x_tick.checked_add(step) {
             Some(next) if next > x_tick => x_tick = next,
             _ => break,
         }
     }
- 
+
     // Draw horizontal lines and y-labels
-    let mut y_tick = 0u32;
-    while y_tick <= h {
+    let mut y_tick = first(y0);
+    while y_tick <= y1 {
         let y = y_tick.min(h.saturating_sub(1)) as f32;
-        draw_line_segment_mut(&mut rgba, (0.0, y), (w as f32, y), grid);
- 
-        if opts.label_every > 0 && ((y_tick / opts.step) % opts.label_every == 0) {
-            // Label "y=<num>" at the left edge at (pad, y+pad)
+        draw_line_segment_mut(rgba, (x0 as f32, y), (x1 as f32, y), grid);
+
+        if opts.label_every > 0 && (y_tick / step).is_multiple_of(opts.label_every) {
+            // Label "<num>" at the left edge of the region at (x0+pad, y+pad)
             let label = format!("{}", y_tick);
-            let lx = pad.min(w.saturating_sub(1));
+            let lx = x0.saturating_add(pad).min(w.saturating_sub(1));
             let ly = y_tick.saturating_add(pad).min(h.saturating_sub(1));
-            draw_text_bitmap(&mut rgba, lx as i32, ly as i32, &label, text, opts.font_scale);
+            draw_text_bitmap(&mut rgba.0, lx as i32, ly as i32, &label, text, opts.font_scale);
         }
- 
-        match y_tick.checked_add(opts.step) {
+
+        match y_tick.checked_add(step) {
             Some(next) if next > y_tick => y_tick = next,
             _ => break,
         }
     }
- 
-    // Encode back to PNG
+}
+
+/// Mark `target` = (x, y, w, h) in image pixels with a box, put `caption` in a banner on
+/// the far side of the image from it, and draw an arrow from the banner to the box.
+/// Returns new PNG bytes. The caption is upper-cased and word-wrapped to the image width.
+pub fn annotate_target(png_bytes: &[u8], target: (u32, u32, u32, u32), caption: &str) -> Result<Vec<u8>> {
+    let img = image::load_from_memory(png_bytes).context("decode PNG")?;
+    let mut rgba = img.to_rgba8();
+    let (w, h) = rgba.dimensions();
+
+    let mark = Rgba([230, 30, 30, 255]);
+    let banner_bg = Rgba([0, 0, 0, 200]);
+    let white = Rgba([255, 255, 255, 255]);
+    let scale = if w >= 1600 { 3 } else { 2 };
+    let pad = 4 * scale;
+    let advance = 6 * scale;
+    let line_h = 9 * scale;
+
+    // Clamp the target box to the image
+    let (tx, ty, tw, th) = target;
+    let x0 = tx.min(w.saturating_sub(1));
+    let y0 = ty.min(h.saturating_sub(1));
+    let x1 = tx.saturating_add(tw.max(1)).min(w.saturating_sub(1)).max(x0);
+    let y1 = ty.saturating_add(th.max(1)).min(h.saturating_sub(1)).max(y0);
+    for inset in 0..(scale + 1) {
+        draw_rect_outline(&mut rgba, (x0.saturating_sub(inset), y0.saturating_sub(inset)), (x1 + inset, y1 + inset), mark);
+    }
+
+    // Caption banner: top of the image when the target sits in its lower half, else bottom
+    let max_chars = (w.saturating_sub(2 * pad) / advance).max(1) as usize;
+    let lines = wrap_words(&caption.to_uppercase(), max_chars);
+    let banner_h = (lines.len() as u32 * line_h + 2 * pad).min(h);
+    let target_cy = (y0 + y1) / 2;
+    let banner_top = if target_cy > h / 2 { 0 } else { h - banner_h };
+    {
+        let mut blended = Blend(rgba);
+        for y in banner_top..banner_top + banner_h {
+            draw_line_segment_mut(&mut blended, (0.0, y as f32), (w as f32, y as f32), banner_bg);
+        }
+        rgba = blended.0;
+    }
+    for (i, line) in lines.iter().enumerate() {
+        let ly = banner_top + pad + i as u32 * line_h;
+        draw_text_bitmap(&mut rgba, pad as i32, ly as i32, line, white, scale);
+    }
+
+    // Arrow from the banner edge to the nearest side of the box
+    let cx = ((x0 + x1) / 2) as f32;
+    let (from, to) = if banner_top == 0 {
+        ((cx, banner_h as f32), (cx, y0.saturating_sub(scale + 1) as f32))
+    } else {
+        ((cx, banner_top as f32), (cx, (y1 + scale + 1) as f32))
+    };
+    draw_arrow(&mut rgba, from, to, mark, scale);
+
     let mut out = Vec::new();
     DynamicImage::ImageRgba8(rgba)
         .write_to(&mut std::io::Cursor::new(&mut out), ImageOutputFormat::Png)
         .context("encode annotated PNG")?;
- 
-    if opts.save_debug {
-        let _ = std::fs::write("screenshot_grid.png", &out);
-    }
     Ok(out)
 }
- 
+
+fn draw_rect_outline(img: &mut RgbaImage, (x0, y0): (u32, u32), (x1, y1): (u32, u32), color: Rgba<u8>) {
+    let (x0, y0, x1, y1) = (x0 as f32, y0 as f32, x1 as f32, y1 as f32);
+    draw_line_segment_mut(img, (x0, y0), (x1, y0), color);
+    draw_line_segment_mut(img, (x1, y0), (x1, y1), color);
+    draw_line_segment_mut(img, (x1, y1), (x0, y1), color);
+    draw_line_segment_mut(img, (x0, y1), (x0, y0), color);
+}
+
+/// Shaft `thickness` px wide plus a two-stroke head at `to`.
+fn draw_arrow(img: &mut RgbaImage, from: (f32, f32), to: (f32, f32), color: Rgba<u8>, thickness: u32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len < 1.0 {
+        return;
+    }
+    let (ux, uy) = (dx / len, dy / len);
+    let head = (len * 0.3).clamp(6.0, 24.0);
+    let half = thickness as f32 / 2.0;
+    for i in 0..thickness {
+        // Offset perpendicular to the shaft
+        let o = i as f32 - half;
+        let (ox, oy) = (-uy * o, ux * o);
+        draw_line_segment_mut(img, (from.0 + ox, from.1 + oy), (to.0 + ox, to.1 + oy), color);
+        for side in [-1.0f32, 1.0] {
+            // ±30° from the reversed shaft direction
+            let (c, s) = (0.866f32, 0.5f32 * side);
+            let hx = -(ux * c - uy * s) * head;
+            let hy = -(uy * c + ux * s) * head;
+            draw_line_segment_mut(img, (to.0 + ox, to.1 + oy), (to.0 + hx + ox, to.1 + hy + oy), color);
+        }
+    }
+}
+
+/// Greedy word wrap to `max_chars` per line; words longer than a line are split.
+fn wrap_words(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut cur = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > max_chars {
+            if !cur.is_empty() {
+                lines.push(std::mem::take(&mut cur));
+            }
+            lines.push(word.drain(..max_chars).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if word.is_empty() {
+            continue;
+        }
+        if cur.is_empty() {
+            cur = word;
+        } else if cur.chars().count() + 1 + word.chars().count() <= max_chars {
+            cur.push(' ');
+            cur.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut cur, word));
+        }
+    }
+    if !cur.is_empty() || lines.is_empty() {
+        lines.push(cur);
+    }
+    lines
+}
+
 // ---------------------- Tiny 5x7 bitmap font ----------------------
  
 #[rustfmt::skip]
@@ -111,6 +337,46 @@ const BITMAP_5X7: &[(&str, [u8; 7])] = &[
     ("y", [0b00000,0b10001,0b10001,0b01111,0b00001,0b00010,0b01100]),
     // '=' sign
     ("=", [0b00000,0b00000,0b11111,0b00000,0b11111,0b00000,0b00000]),
+    // Upper case + punctuation for AnnotateShot captions (drawn upper-cased)
+    ("A", [0b01110,0b10001,0b10001,0b11111,0b10001,0b10001,0b10001]),
+    ("B", [0b11110,0b10001,0b10001,0b11110,0b10001,0b10001,0b11110]),
+    ("C", [0b01110,0b10001,0b10000,0b10000,0b10000,0b10001,0b01110]),
+    ("D", [0b11100,0b10010,0b10001,0b10001,0b10001,0b10010,0b11100]),
+    ("E", [0b11111,0b10000,0b10000,0b11110,0b10000,0b10000,0b11111]),
+    ("F", [0b11111,0b10000,0b10000,0b11110,0b10000,0b10000,0b10000]),
+    ("G", [0b01110,0b10001,0b10000,0b10111,0b10001,0b10001,0b01111]),
+    ("H", [0b10001,0b10001,0b10001,0b11111,0b10001,0b10001,0b10001]),
+    ("I", [0b01110,0b00100,0b00100,0b00100,0b00100,0b00100,0b01110]),
+    ("J", [0b00111,0b00010,0b00010,0b00010,0b00010,0b10010,0b01100]),
+    ("K", [0b10001,0b10010,0b10100,0b11000,0b10100,0b10010,0b10001]),
+    ("L", [0b10000,0b10000,0b10000,0b10000,0b10000,0b10000,0b11111]),
+    ("M", [0b10001,0b11011,0b10101,0b10101,0b10001,0b10001,0b10001]),
+    ("N", [0b10001,0b10001,0b11001,0b10101,0b10011,0b10001,0b10001]),
+    ("O", [0b01110,0b10001,0b10001,0b10001,0b10001,0b10001,0b01110]),
+    ("P", [0b11110,0b10001,0b10001,0b11110,0b10000,0b10000,0b10000]),
+    ("Q", [0b01110,0b10001,0b10001,0b10001,0b10101,0b10010,0b01101]),
+    ("R", [0b11110,0b10001,0b10001,0b11110,0b10100,0b10010,0b10001]),
+    ("S", [0b01111,0b10000,0b10000,0b01110,0b00001,0b00001,0b11110]),
+    ("T", [0b11111,0b00100,0b00100,0b00100,0b00100,0b00100,0b00100]),
+    ("U", [0b10001,0b10001,0b10001,0b10001,0b10001,0b10001,0b01110]),
+    ("V", [0b10001,0b10001,0b10001,0b10001,0b10001,0b01010,0b00100]),
+    ("W", [0b10001,0b10001,0b10001,0b10101,0b10101,0b10101,0b01010]),
+    ("X", [0b10001,0b10001,0b01010,0b00100,0b01010,0b10001,0b10001]),
+    ("Y", [0b10001,0b10001,0b01010,0b00100,0b00100,0b00100,0b00100]),
+    ("Z", [0b11111,0b00001,0b00010,0b00100,0b01000,0b10000,0b11111]),
+    (".", [0b00000,0b00000,0b00000,0b00000,0b00000,0b01100,0b01100]),
+    (",", [0b00000,0b00000,0b00000,0b00000,0b01100,0b00100,0b01000]),
+    (":", [0b00000,0b01100,0b01100,0b00000,0b01100,0b01100,0b00000]),
+    ("-", [0b00000,0b00000,0b00000,0b11111,0b00000,0b00000,0b00000]),
+    ("'", [0b00100,0b00100,0b01000,0b00000,0b00000,0b00000,0b00000]),
+    ("?", [0b01110,0b10001,0b00001,0b00010,0b00100,0b00000,0b00100]),
+    ("!", [0b00100,0b00100,0b00100,0b00100,0b00100,0b00000,0b00100]),
+    ("/", [0b00001,0b00010,0b00010,0b00100,0b01000,0b01000,0b10000]),
+    ("(", [0b00010,0b00100,0b01000,0b01000,0b01000,0b00100,0b00010]),
+    (")", [0b01000,0b00100,0b00010,0b00010,0b00010,0b00100,0b01000]),
+    ("&", [0b01100,0b10010,0b10100,0b01000,0b10101,0b10010,0b01101]),
+    ("#", [0b01010,0b01010,0b11111,0b01010,0b11111,0b01010,0b01010]),
+    ("$", [0b00100,0b01111,0b10100,0b01110,0b00101,0b11110,0b00100]),
 ];
  
 fn glyph_rows(ch: char) -> Option<[u8; 7]> {
@@ -141,7 +407,7 @@ fn draw_char(img: &mut RgbaImage, x: i32, y: i32, ch: char, color: Rgba<u8>, sca
                         let sx = px + dx as i32;
                         let sy = py + dy as i32;
                         if sx >= 0 && sy >= 0 && (sx as u32) < w && (sy as u32) < h {
-                            img.put_pixel(sx as u32, sy as u32, color);
+                            img.get_pixel_mut(sx as u32, sy as u32).blend(&color);
                         }
                     }
                 }
@@ -150,7 +416,7 @@ fn draw_char(img: &mut RgbaImage, x: i32, y: i32, ch: char, color: Rgba<u8>, sca
     }
 }
  
-/// Draw simple ASCII text (allowed chars: 0-9, x, y, =)
+/// Draw simple ASCII text (0-9, x, y, =, A-Z and the punctuation above; others are blank)
 fn draw_text_bitmap(
     img: &mut RgbaImage,
     mut x: i32,
@@ -165,3 +431,41 @@ fn draw_text_bitmap(
         x += advance;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white_png(w: u32, h: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(w, h, Rgba([255, 255, 255, 255])))
+            .write_to(&mut std::io::Cursor::new(&mut out), ImageOutputFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn wrap_words_fills_lines_and_splits_long_words() {
+        assert_eq!(wrap_words("CLICK THE SAVE BUTTON", 10), ["CLICK THE", "SAVE", "BUTTON"]);
+        assert_eq!(wrap_words("ABCDEFGHIJKL NEXT", 5), ["ABCDE", "FGHIJ", "KL", "NEXT"]);
+        assert_eq!(wrap_words("", 5), [""]);
+    }
+
+    #[test]
+    fn banner_sits_on_the_far_side_from_the_target() {
+        let (w, h) = (400, 300);
+        let dark = |png: &[u8], x: u32, y: u32| {
+            image::load_from_memory(png).unwrap().to_rgba8().get_pixel(x, y).0[0] < 128
+        };
+
+        // Target in the lower half → banner along the top edge
+        let low = annotate_target(&white_png(w, h), (150, 220, 40, 20), "Save").unwrap();
+        assert!(dark(&low, 1, 1));
+        assert!(!dark(&low, 1, h - 2));
+
+        // Target in the upper half → banner along the bottom edge
+        let high = annotate_target(&white_png(w, h), (150, 30, 40, 20), "Save").unwrap();
+        assert!(!dark(&high, 1, 1));
+        assert!(dark(&high, 1, h - 2));
+    }
+}
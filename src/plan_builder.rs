@@ -0,0 +1,223 @@
+// src/plan_builder.rs
+//
+// Fluent construction of step lists:
+//
+//     let steps = AutomationPlan::builder()
+//         .visit("https://portal.example.com")
+//         .wait_for("button[type='submit']", 20)
+//         .click_llm("Click the Sign in button")
+//         .update_cell(row, col, "Y", CellStatus::Green)
+//         .build()?;
+//
+// Every step is a `Step` literal, so a field added to a variant breaks the build here
+// instead of surfacing at run time; optional fields get the same defaults as plan files.
+
+use anyhow::Result;
+
+use crate::openai_client::TextMatch;
+use crate::plan::{AutomationPlan, Step};
+use crate::plan_loader::validate_steps;
+use crate::sheets::CellStatus;
+
+#[derive(Default)]
+pub struct PlanBuilder {
+    steps: Vec<Step>,
+}
+
+impl AutomationPlan {
+    pub fn builder() -> PlanBuilder {
+        PlanBuilder::default()
+    }
+}
+
+impl PlanBuilder {
+    /// Append any step as-is.
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn begin_client(self, row: usize) -> Self {
+        self.step(Step::BeginClient { row })
+    }
+
+    pub fn visit(self, url: &str) -> Self {
+        self.step(Step::VisitUrl { url: url.to_string() })
+    }
+
+    pub fn go_back(self) -> Self {
+        self.step(Step::GoBack)
+    }
+
+    pub fn reload(self) -> Self {
+        self.step(Step::Reload)
+    }
+
+    pub fn wait(self, secs: u64) -> Self {
+        self.step(Step::Wait(secs))
+    }
+
+    pub fn wait_for(self, selector: &str, timeout_secs: u64) -> Self {
+        self.step(Step::WaitForElement {
+            selector: selector.to_string(),
+            timeout_secs,
+            poll_ms: 250,
+        })
+    }
+
+    /// Wait out a loading indicator: pass once nothing matching `selector` is displayed.
+    pub fn wait_hidden(self, selector: &str, timeout_secs: u64) -> Self {
+        self.step(Step::WaitForHidden { selector: selector.to_string(), timeout_secs })
+    }
+
+    pub fn click_llm(self, prompt: &str) -> Self {
+        self.step(Step::ClickByLlm {
+            prompt: prompt.to_string(),
+            double: None,
+            validation_question: None,
+            max_click_retries: 0,
+        })
+    }
+
+    pub fn click_dom(self, prompt: &str) -> Self {
+        self.step(Step::ClickByDom { prompt: prompt.to_string(), double: None, nth: None })
+    }
+
+    /// Deterministic click on the one control labelled exactly `text`.
+    pub fn click_text(self, text: &str) -> Self {
+        self.step(Step::ClickByText {
+            text: text.to_string(),
+            r#match: TextMatch::Exact,
+            double: None,
+            nth: None,
+        })
+    }
+
+    pub fn hover(self, prompt: &str) -> Self {
+        self.step(Step::Hover { prompt: prompt.to_string(), dwell_ms: None })
+    }
+
+    pub fn type_text(self, text: &str) -> Self {
+        self.step(Step::TypeText { text: text.to_string(), per_char_delay_ms: 12 })
+    }
+
+    /// Give `selector` keyboard focus before OS-level `type_text` / `TypeOTP`.
+    pub fn focus(self, selector: &str) -> Self {
+        self.step(Step::Focus { selector: selector.to_string() })
+    }
+
+    pub fn type_into(self, selector: &str, text: &str) -> Self {
+        self.step(Step::TypeInto {
+            selector: selector.to_string(),
+            text: text.to_string(),
+            clear_first: true,
+        })
+    }
+
+    /// Clear and type each `(selector, value)` pair in order.
+    pub fn fill_form(self, fields: &[(&str, &str)]) -> Self {
+        self.step(Step::FillForm {
+            fields: fields.iter().map(|(s, v)| (s.to_string(), v.to_string())).collect(),
+            delay_ms: None,
+        })
+    }
+
+    pub fn type_key(self, key: &str) -> Self {
+        self.step(Step::TypeKey { key: key.to_string() })
+    }
+
+    pub fn screenshot(self, name: &str) -> Self {
+        self.step(Step::Screenshot { name: name.to_string() })
+    }
+
+    /// Docs screenshot with the control matching `prompt` boxed and `caption` overlaid.
+    pub fn annotate(self, prompt: &str, caption: &str) -> Self {
+        self.step(Step::AnnotateShot { prompt: prompt.to_string(), caption: caption.to_string() })
+    }
+
+    pub fn assert(self, question: &str, on_fail: &str) -> Self {
+        self.step(Step::Assert {
+            question: question.to_string(),
+            on_fail: on_fail.to_string(),
+        })
+    }
+
+    /// Fail unless `substring` appears in the page source within `timeout_secs`.
+    pub fn assert_text(self, substring: &str, timeout_secs: u64) -> Self {
+        self.step(Step::AssertText {
+            substring: substring.to_string(),
+            present: true,
+            timeout_secs,
+            selector: None,
+        })
+    }
+
+    /// Block until the URL contains `to_contains` (e.g. after a navigation click).
+    pub fn wait_for_url(self, to_contains: &str, timeout_secs: u64) -> Self {
+        self.step(Step::WaitForUrlChange {
+            from_contains: None,
+            to_contains: Some(to_contains.to_string()),
+            timeout_secs,
+        })
+    }
+
+    pub fn window_size(self, width: u32, height: u32) -> Self {
+        self.step(Step::SetWindowSize { width, height })
+    }
+
+    pub fn update_cell(self, row: usize, col: usize, value: &str, status: CellStatus) -> Self {
+        self.step(Step::UpdateSheetCell {
+            row,
+            col,
+            value: value.to_string(),
+            status,
+            note: None,
+        })
+    }
+
+    /// Read the cell back and fail unless it equals `expected` (trimmed).
+    pub fn verify_cell(self, row: usize, col: usize, expected: &str) -> Self {
+        self.step(Step::VerifySheetCell { row, col, expected: expected.to_string() })
+    }
+
+    pub fn stop_client(self) -> Self {
+        self.step(Step::StopClient)
+    }
+
+    /// Finish the plan, running the same checks as PLAN_FILE plans.
+    pub fn build(self) -> Result<Vec<Step>> {
+        validate_steps(&self.steps)?;
+        Ok(self.steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_keep_their_order_and_defaults() {
+        let steps = AutomationPlan::builder()
+            .visit("https://portal.example.com")
+            .click_text("Sign in")
+            .type_text("hello")
+            .wait(2)
+            .build()
+            .unwrap();
+        assert_eq!(steps.len(), 4);
+        assert!(matches!(&steps[0], Step::VisitUrl { url } if url == "https://portal.example.com"));
+        assert!(matches!(
+            &steps[1],
+            Step::ClickByText { text, r#match: TextMatch::Exact, double: None, nth: None } if text == "Sign in"
+        ));
+        assert!(matches!(&steps[2], Step::TypeText { per_char_delay_ms: 12, .. }));
+        assert!(matches!(&steps[3], Step::Wait(2)));
+    }
+
+    #[test]
+    fn build_runs_plan_file_checks() {
+        assert!(AutomationPlan::builder().build().is_err());
+        let err = AutomationPlan::builder().update_cell(0, 3, "Y", CellStatus::Green).build().unwrap_err();
+        assert!(err.to_string().contains("UpdateSheetCell"), "{err}");
+    }
+}
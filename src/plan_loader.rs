@@ -0,0 +1,235 @@
+// src/plan_loader.rs
+//
+// Load a plan (list of `Step`s, serde tag "type") from a JSON or YAML file so
+// automation flows can be edited without recompiling.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::plan::{AutomationPlan, Step};
+
+impl AutomationPlan {
+    /// Plan from a PLAN_FILE-style JSON/YAML file (see `load_steps`).
+    pub fn from_file(path: &str) -> Result<Self> {
+        Ok(AutomationPlan { steps: load_steps(path)? })
+    }
+}
+
+/// Read and validate steps from `path`. `.yaml` / `.yml` are parsed as YAML, anything else as JSON.
+/// The file is either a bare list of steps or an object with a `steps` list.
+pub fn load_steps(path: &str) -> Result<Vec<Step>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read plan file {path}"))?;
+
+    let is_yaml = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map_or(false, |e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"));
+
+    let steps = parse_steps(&raw, is_yaml).with_context(|| format!("Invalid plan in {path}"))?;
+    validate_steps(&steps).with_context(|| format!("Invalid plan in {path}"))?;
+    println!("📄 Loaded {} steps from {path}", steps.len());
+    Ok(steps)
+}
+
+fn parse_steps(raw: &str, is_yaml: bool) -> Result<Vec<Step>> {
+    let doc: Value = if is_yaml {
+        serde_yaml::from_str(raw).context("not valid YAML")?
+    } else {
+        serde_json::from_str(raw).context("not valid JSON")?
+    };
+    let list = match doc {
+        Value::Object(mut o) if o.contains_key("steps") => o.remove("steps").unwrap_or_default(),
+        other => other,
+    };
+    steps_from_value(list)
+}
+
+fn steps_from_value(list: Value) -> Result<Vec<Step>> {
+    let Value::Array(items) = list else {
+        bail!("expected a list of steps");
+    };
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| step_from_value(v).with_context(|| format!("step {}", i + 1)))
+        .collect()
+}
+
+/// `Wait` is a tuple variant, which serde's "type" tag can't carry, so plan files spell
+/// it `{"type": "Wait", "secs": N}` and it is built here; `Branch` is walked so the same
+/// applies to nested steps.
+fn step_from_value(v: Value) -> Result<Step> {
+    match v.get("type").and_then(Value::as_str) {
+        Some("Wait") => {
+            let secs = v.get("secs").and_then(Value::as_u64).context("Wait needs whole-number `secs`")?;
+            Ok(Step::Wait(secs))
+        }
+        Some("Branch") => {
+            let Value::Object(mut o) = v else { unreachable!() };
+            let question = match o.remove("question") {
+                Some(Value::String(q)) => q,
+                _ => bail!("Branch needs a `question` string"),
+            };
+            let mut nested = |key: &str| match o.remove(key) {
+                None | Some(Value::Null) => Ok(Vec::new()),
+                Some(list) => steps_from_value(list).with_context(|| format!("Branch {key}")),
+            };
+            let then_steps = nested("then_steps")?;
+            let else_steps = nested("else_steps")?;
+            Ok(Step::Branch { question, then_steps, else_steps })
+        }
+        _ => Ok(serde_json::from_value(v)?),
+    }
+}
+
+/// Serde already rejects missing fields; this catches ones that are present but empty.
+pub(crate) fn validate_steps(steps: &[Step]) -> Result<()> {
+    if steps.is_empty() {
+        bail!("plan has no steps");
+    }
+
+    for (i, step) in steps.iter().enumerate() {
+        let n = i + 1;
+        match step {
+            Step::VisitUrl { url, .. } if url.trim().is_empty() => {
+                bail!("step {n} (VisitUrl): url is empty")
+            }
+            Step::ClickByLlm { prompt, .. } | Step::ClickByDom { prompt, .. }
+                if prompt.trim().is_empty() =>
+            {
+                bail!("step {n}: click prompt is empty")
+            }
+            Step::WaitForElement { selector, .. }
+            | Step::WaitForHidden { selector, .. }
+            | Step::SelectDropdown { selector, .. }
+            | Step::TypeInto { selector, .. }
+            | Step::Focus { selector }
+                if selector.trim().is_empty() =>
+            {
+                bail!("step {n}: selector is empty")
+            }
+            Step::ClickInRow { row_text, control_prompt, .. }
+                if row_text.trim().is_empty() || control_prompt.trim().is_empty() =>
+            {
+                bail!("step {n} (ClickInRow): row_text and control_prompt are required")
+            }
+            Step::Branch { question, then_steps, else_steps } => {
+                if question.trim().is_empty() {
+                    bail!("step {n} (Branch): question is empty");
+                }
+                for (label, nested) in [("then_steps", then_steps), ("else_steps", else_steps)] {
+                    if !nested.is_empty() {
+                        validate_steps(nested).with_context(|| format!("step {n} (Branch) {label}"))?;
+                    }
+                }
+            }
+            Step::Assert { question, .. } if question.trim().is_empty() => {
+                bail!("step {n} (Assert): question is empty")
+            }
+            Step::ClickByText { text, .. } if text.trim().is_empty() => {
+                bail!("step {n} (ClickByText): text is empty")
+            }
+            Step::ClickByText { nth: Some(0), .. } => {
+                bail!("step {n} (ClickByText): nth is 1-based")
+            }
+            Step::ClickByDom { nth: Some(0), .. } => {
+                bail!("step {n} (ClickByDom): nth is 1-based")
+            }
+            Step::AssertText { substring, .. } if substring.is_empty() => {
+                bail!("step {n} (AssertText): substring is empty")
+            }
+            Step::DragAndDrop { from_prompt, to_prompt }
+                if from_prompt.trim().is_empty() || to_prompt.trim().is_empty() =>
+            {
+                bail!("step {n} (DragAndDrop): from_prompt and to_prompt are required")
+            }
+            Step::FillForm { fields, .. } => {
+                if fields.is_empty() {
+                    bail!("step {n} (FillForm): fields is empty");
+                }
+                if let Some(i) = fields.iter().position(|(sel, _)| sel.trim().is_empty()) {
+                    bail!("step {n} (FillForm): field {} has an empty selector", i + 1);
+                }
+            }
+            Step::WaitForUrlChange { from_contains: Some(f), .. } if f.is_empty() => {
+                bail!("step {n} (WaitForUrlChange): from_contains is empty")
+            }
+            Step::WaitForUrlChange { to_contains: Some(t), .. } if t.is_empty() => {
+                bail!("step {n} (WaitForUrlChange): to_contains is empty")
+            }
+            Step::WaitForNetworkIdle { idle_ms, timeout_secs }
+                if *idle_ms == 0 || *idle_ms >= timeout_secs.saturating_mul(1000) =>
+            {
+                bail!("step {n} (WaitForNetworkIdle): idle_ms must be > 0 and shorter than timeout_secs")
+            }
+            Step::SetWindowSize { width, height } if *width < 200 || *height < 200 => {
+                bail!("step {n} (SetWindowSize): {width}x{height} is too small to be a browser window")
+            }
+            Step::EnsureAbsent { text, .. } if text.is_empty() => {
+                bail!("step {n} (EnsureAbsent): text is empty")
+            }
+            Step::EnsureAbsent { row: Some(0), .. } | Step::EnsureAbsent { col: Some(0), .. } => {
+                bail!("step {n} (EnsureAbsent): row/col are 1-based")
+            }
+            Step::Hover { prompt, .. } if prompt.trim().is_empty() => {
+                bail!("step {n} (Hover): prompt is empty")
+            }
+            Step::Screenshot { name } if name.trim().is_empty() => {
+                bail!("step {n} (Screenshot): name is empty")
+            }
+            Step::AnnotateShot { prompt, .. } if prompt.trim().is_empty() => {
+                bail!("step {n} (AnnotateShot): prompt is empty")
+            }
+            Step::AnnotateShot { caption, .. } if caption.trim().is_empty() => {
+                bail!("step {n} (AnnotateShot): caption is empty")
+            }
+            Step::UpdateSheetCell { row, col, .. } if *row == 0 || *col == 0 => {
+                bail!("step {n} (UpdateSheetCell): row/col are 1-based")
+            }
+            Step::VerifySheetCell { row, col, .. } if *row == 0 || *col == 0 => {
+                bail!("step {n} (VerifySheetCell): row/col are 1-based")
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_plan_loads() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/plan.example.json");
+        let steps = load_steps(path).expect("examples/plan.example.json no longer matches Step");
+        assert_eq!(steps.len(), 7);
+    }
+
+    #[test]
+    fn empty_field_is_rejected() {
+        let steps: Vec<Step> =
+            serde_json::from_value(serde_json::json!([{ "type": "Screenshot", "name": "  " }])).unwrap();
+        let err = validate_steps(&steps).unwrap_err().to_string();
+        assert!(err.contains("Screenshot"), "{err}");
+    }
+
+    #[test]
+    fn wait_loads_at_top_level_and_inside_branch() {
+        let yaml = "steps:\n  - { type: Wait, secs: 3 }\n  - type: Branch\n    question: Is a banner shown?\n    then_steps:\n      - { type: Wait, secs: 1 }\n";
+        let steps = parse_steps(yaml, true).unwrap();
+        assert!(matches!(steps[0], Step::Wait(3)));
+        match &steps[1] {
+            Step::Branch { then_steps, else_steps, .. } => {
+                assert!(matches!(then_steps[..], [Step::Wait(1)]));
+                assert!(else_steps.is_empty());
+            }
+            other => panic!("expected Branch, got {other:?}"),
+        }
+
+        let err = parse_steps(r#"[{ "type": "Wait" }]"#, false).unwrap_err();
+        assert!(format!("{err:#}").contains("step 1"), "{err:#}");
+    }
+}
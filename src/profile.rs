@@ -0,0 +1,64 @@
+// src/profile.rs
+//
+// Named per-environment profiles from config.toml (CONFIG_FILE overrides the path):
+//
+//     [profiles.staging]
+//     login_url = "https://staging.portal.example.com/login"
+//     sheets_id = "1AbC..."
+//     sheets_range = "Staging!A1:T"
+//     openai_model = "gpt-4o-mini"
+//     click_x_offset_px = 0
+//     chrome_window_width = 1600
+//
+// Keys are env var names (any case). Selecting a profile (--profile / PROFILE) sets those
+// vars before the CLI flags are applied, so precedence is
+// CLI flag > profile > env var (incl. .env) > built-in default.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, BTreeMap<String, toml::Value>>,
+}
+
+/// Apply profile `name` (else PROFILE) from CONFIG_FILE (default config.toml).
+/// No profile selected is a no-op; a selected profile that is missing is an error.
+pub fn apply_profile(name: Option<&str>) -> Result<()> {
+    let Some(name) = name
+        .map(str::to_string)
+        .or_else(|| std::env::var("PROFILE").ok())
+        .filter(|n| !n.trim().is_empty())
+    else {
+        return Ok(());
+    };
+    let path = std::env::var("CONFIG_FILE").unwrap_or_else(|_| "config.toml".to_string());
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("profile '{name}' selected but {path} could not be read"))?;
+    let config: ConfigFile = toml::from_str(&raw).with_context(|| format!("Invalid TOML in {path}"))?;
+
+    let Some(settings) = config.profiles.get(name.trim()) else {
+        let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        bail!("profile '{name}' not found in {path} (profiles: {})", known.join(", "));
+    };
+
+    let mut keys = Vec::with_capacity(settings.len());
+    for (key, value) in settings {
+        let var = key.trim().to_ascii_uppercase();
+        let value = match value {
+            toml::Value::String(s) => s.clone(),
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Float(f) => f.to_string(),
+            // Flags in this codebase are "1"/"0"
+            toml::Value::Boolean(b) => if *b { "1" } else { "0" }.to_string(),
+            other => bail!("profile '{name}' key '{key}': expected a string, number or bool, got {}", other.type_str()),
+        };
+        std::env::set_var(&var, value);
+        keys.push(var);
+    }
+    // Names only: profiles may carry secrets
+    println!("🗂️ Profile '{name}' from {path}: {}", keys.join(", "));
+    Ok(())
+}
@@ -0,0 +1,23 @@
+// src/secrets.rs
+//
+// Secrets from mounted files rather than the environment: NAME_FILE points at a file
+// whose trimmed contents are the value, and wins over NAME when both are set. Keeps
+// keys out of process listings and env dumps.
+
+use anyhow::{bail, Context, Result};
+
+/// Value of secret `name`: the contents of `{name}_FILE` if set, else `$name`.
+/// Ok(None) when neither is set; an unreadable or empty file is an error.
+pub fn secret_from_env(name: &str) -> Result<Option<String>> {
+    let file_var = format!("{name}_FILE");
+    if let Some(path) = std::env::var(&file_var).ok().filter(|p| !p.trim().is_empty()) {
+        let value = std::fs::read_to_string(path.trim())
+            .with_context(|| format!("cannot read {file_var} ({path})"))?;
+        let value = value.trim();
+        if value.is_empty() {
+            bail!("{file_var} ({path}) is empty");
+        }
+        return Ok(Some(value.to_string()));
+    }
+    Ok(std::env::var(name).ok().filter(|v| !v.trim().is_empty()))
+}
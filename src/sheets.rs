@@ -23,14 +23,124 @@ struct SheetProps {
     title: String,
 }
 
+/// Status shown on a sheet cell via its background color.
+/// In plan files: "Green", "Red", ... or {"Custom": [r, g, b]}.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum CellStatus {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+    Gray,
+    Custom(u8, u8, u8),
+}
+
+impl CellStatus {
+    /// Background color written for this status.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            CellStatus::Green => (0, 255, 0),
+            CellStatus::Red => (255, 0, 0),
+            CellStatus::Yellow => (255, 255, 0),
+            CellStatus::Blue => (0, 120, 255),
+            CellStatus::Gray => (200, 200, 200),
+            CellStatus::Custom(r, g, b) => (r, g, b),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CellStatus::Green => "success",
+            CellStatus::Red => "failed",
+            CellStatus::Yellow => "warning",
+            CellStatus::Blue => "in progress",
+            CellStatus::Gray => "skipped",
+            CellStatus::Custom(..) => "custom",
+        }
+    }
+}
+
+impl std::fmt::Display for CellStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CellStatus::Custom(r, g, b) => write!(f, "custom #{r:02X}{g:02X}{b:02X}"),
+            other => write!(f, "{}", other.name()),
+        }
+    }
+}
+
+/// One cell write for `SheetsClient::update_cells_batch` (1-based row/col).
+#[derive(Debug, Clone)]
+pub struct CellUpdate {
+    pub row: usize,
+    pub col: usize,
+    pub value: String,
+    pub color: (u8, u8, u8),
+    /// Hover note for the cell (e.g. the failure reason); None leaves any existing note
+    pub note: Option<String>,
+}
+
 pub struct SheetsClient {
     http: reqwest::Client,
     spreadsheet_id: String,
     sheet_name: String,
     sheet_id: i32,
     sa_path: String,
+    /// Last access token and when it was fetched (see SHEETS_TOKEN_TTL_SECS)
+    token_cache: std::sync::Mutex<Option<(String, std::time::Instant)>>,
 }
 
+/// Non-success HTTP status from a Sheets call (body kept: Google puts the reason there).
+#[derive(Debug)]
+struct SheetsHttpError {
+    what: String,
+    status: reqwest::StatusCode,
+    body: String,
+    /// `error.message` / `error.status` from Google's JSON error body, when it parses
+    google: Option<GoogleErrorDetail>,
+}
+
+/// Google API error envelope: {"error": {"code": 400, "message": "...", "status": "INVALID_ARGUMENT"}}
+#[derive(Debug, Deserialize)]
+struct GoogleErrorBody {
+    error: GoogleErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleErrorDetail {
+    #[serde(default)]
+    code: Option<u16>,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+impl SheetsHttpError {
+    fn new(what: &str, status: reqwest::StatusCode, body: String) -> Self {
+        let google = serde_json::from_str::<GoogleErrorBody>(&body).ok().map(|b| b.error);
+        Self { what: what.to_string(), status, body, google }
+    }
+}
+
+impl std::fmt::Display for SheetsHttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.google {
+            Some(g) if !g.message.is_empty() => write!(
+                f,
+                "Sheets {} failed: {} {}: {}",
+                self.what,
+                g.code.map_or_else(|| self.status.as_u16().to_string(), |c| c.to_string()),
+                g.status.as_deref().unwrap_or("ERROR"),
+                g.message
+            ),
+            _ => write!(f, "Sheets {} failed: {} body={}", self.what, self.status, self.body),
+        }
+    }
+}
+
+impl std::error::Error for SheetsHttpError {}
+
 impl SheetsClient {
     /// Global throttle to avoid Google Sheets write quota (commonly 60 writes/min/user).
     /// Enforces ~1 write / 1100ms across the whole process.
@@ -65,6 +175,84 @@ impl SheetsClient {
         }
     }
 
+    /// Send a Sheets request, retrying transient failures with exponential backoff + jitter (`compute_backoff_ms`).
+    /// Retries 429 (up to 8 attempts, write quota), 500/502/503 and network errors (up to 3).
+    /// Other statuses (400/401/403/404...) fail immediately with the response body.
+    async fn send_with_retry<F>(what: &str, throttle: bool, build: F) -> Result<reqwest::Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        const MAX_TRANSIENT_ATTEMPTS: u32 = 3;
+        const MAX_RATE_LIMIT_ATTEMPTS: u32 = 8;
+
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            if throttle {
+                Self::throttle_write_request().await;
+            }
+
+            let resp = match build().send().await {
+                Ok(r) => r,
+                Err(e) if attempt < MAX_TRANSIENT_ATTEMPTS && (e.is_timeout() || e.is_connect() || e.is_request()) => {
+                    let wait_ms = compute_backoff_ms(&reqwest::header::HeaderMap::new(), attempt);
+                    eprintln!(
+                        "⏳ Sheets {what} network error (attempt {attempt}/{MAX_TRANSIENT_ATTEMPTS}): {e}; retrying in {wait_ms}ms"
+                    );
+                    tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+                    continue;
+                }
+                Err(e) => return Err(e).with_context(|| format!("Sheets {what} request failed")),
+            };
+
+            let status = resp.status();
+            if status.is_success() {
+                return Ok(resp);
+            }
+
+            // Include body: Google puts the real reason there (permissions, invalid sheetId, etc.)
+            let headers = resp.headers().clone();
+            let body = resp.text().await.unwrap_or_default();
+
+            let max_attempts = match status.as_u16() {
+                429 => MAX_RATE_LIMIT_ATTEMPTS,
+                500 | 502 | 503 => MAX_TRANSIENT_ATTEMPTS,
+                _ => 0,
+            };
+            if attempt < max_attempts {
+                let wait_ms = compute_backoff_ms(&headers, attempt);
+                eprintln!(
+                    "⏳ Sheets {what} returned {status} (attempt {attempt}/{max_attempts}); retrying in {wait_ms}ms"
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+                continue;
+            }
+
+            return Err(SheetsHttpError::new(what, status, body).into());
+        }
+    }
+
+    /// `send_with_retry` with the cached bearer token. A 401 (token expired mid-run)
+    /// drops the cached token, re-authenticates once and retries the request.
+    async fn send_authed<F>(&self, what: &str, throttle: bool, build: F) -> Result<reqwest::Response>
+    where
+        F: Fn(&str) -> reqwest::RequestBuilder,
+    {
+        let token = self.bearer_token().await?;
+        match Self::send_with_retry(what, throttle, || build(&token)).await {
+            Err(e)
+                if e.downcast_ref::<SheetsHttpError>()
+                    .map_or(false, |h| h.status == reqwest::StatusCode::UNAUTHORIZED) =>
+            {
+                eprintln!("🔑 Sheets {what} got 401; refreshing access token and retrying once");
+                self.invalidate_token();
+                let token = self.bearer_token().await?;
+                Self::send_with_retry(what, throttle, || build(&token)).await
+            }
+            other => other,
+        }
+    }
+
     pub fn sheet_name(&self) -> &str {
         &self.sheet_name
     }
@@ -74,6 +262,10 @@ impl SheetsClient {
     /// - SHEETS_ID
     /// - GOOGLE_SERVICE_ACCOUNT_JSON (path to SA json)
     /// - SHEETS_SHEET_NAME (recommended) OR SHEETS_RANGE (Tab!A1:T) fallback
+    ///
+    /// Fails unless the write tab exists and is the tab SHEETS_RANGE reads (a range without
+    /// a tab reads the spreadsheet's first tab), so rows are never read from one tab and
+    /// written to another.
     pub async fn new_from_env() -> Result<Self> {
         let spreadsheet_id =
             std::env::var("SHEETS_ID").context("SHEETS_ID must be set")?;
@@ -81,11 +273,11 @@ impl SheetsClient {
         let sa_path = std::env::var("GOOGLE_SERVICE_ACCOUNT_JSON")
             .context("GOOGLE_SERVICE_ACCOUNT_JSON must be set (path to service account JSON)")?;
 
-        let sheet_name = std::env::var("SHEETS_SHEET_NAME").ok().or_else(|| {
-            std::env::var("SHEETS_RANGE")
-                .ok()
-                .and_then(|r| r.split('!').next().map(|s| s.to_string()))
-        }).unwrap_or_else(|| "Sheet1".to_string());
+        let named = std::env::var("SHEETS_SHEET_NAME").ok().filter(|s| !s.trim().is_empty());
+        // Some(None): SHEETS_RANGE is set but names no tab
+        let range_tab = std::env::var("SHEETS_RANGE")
+            .ok()
+            .map(|r| r.contains('!').then(|| sheet_name_of_range(&r)));
 
         let key = yup_oauth2::read_service_account_key(&sa_path)
             .await
@@ -106,8 +298,33 @@ impl SheetsClient {
             .context("Failed to obtain service account access token")?
             .as_ref()
             .to_string();
-        let sheet_id =
-            Self::resolve_sheet_id(&http, &token, &spreadsheet_id, &sheet_name).await?;
+        let tabs = Self::fetch_sheet_tabs(&http, &token, &spreadsheet_id)
+            .await
+            .context("Could not list the spreadsheet's tabs to resolve sheetId")?;
+
+        let read_tab = match range_tab {
+            Some(Some(tab)) => Some(tab),
+            Some(None) => tabs.first().map(|(title, _)| title.clone()),
+            None => None,
+        };
+        let sheet_name = named
+            .or_else(|| read_tab.clone())
+            .unwrap_or_else(|| "Sheet1".to_string());
+        let sheet_id = tabs
+            .iter()
+            .find(|(title, _)| *title == sheet_name)
+            .map(|(_, id)| *id)
+            .with_context(|| {
+                let titles: Vec<&str> = tabs.iter().map(|(t, _)| t.as_str()).collect();
+                format!("Could not find sheet tab named '{sheet_name}' (tabs: {})", titles.join(", "))
+            })?;
+        if let Some(read) = read_tab.filter(|r| *r != sheet_name) {
+            anyhow::bail!(
+                "SHEETS_RANGE reads tab '{read}' but writes would go to '{sheet_name}' \
+                 (SHEETS_SHEET_NAME); point both at the same tab"
+            );
+        }
+        println!("🗂️ Sheet tab '{sheet_name}' (sheetId {sheet_id}) for reads and writes");
 
         Ok(Self {
             http,
@@ -115,10 +332,32 @@ impl SheetsClient {
             sheet_name,
             sheet_id,
             sa_path,
+            token_cache: std::sync::Mutex::new(Some((token, std::time::Instant::now()))),
         })
     }
 
+    /// Cached access token; re-authenticates once it is older than SHEETS_TOKEN_TTL_SECS
+    /// (default 3000s, under the 1h lifetime of service-account tokens).
     async fn bearer_token(&self) -> Result<String> {
+        let ttl = std::time::Duration::from_secs(
+            std::env::var("SHEETS_TOKEN_TTL_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(3000),
+        );
+        if let Some((token, fetched)) = self.token_cache.lock().unwrap().as_ref() {
+            if fetched.elapsed() < ttl {
+                return Ok(token.clone());
+            }
+        }
+
+        let token = self.fetch_token().await?;
+        *self.token_cache.lock().unwrap() = Some((token.clone(), std::time::Instant::now()));
+        Ok(token)
+    }
+
+    fn invalidate_token(&self) {
+        *self.token_cache.lock().unwrap() = None;
+    }
+
+    async fn fetch_token(&self) -> Result<String> {
         let key = yup_oauth2::read_service_account_key(&self.sa_path)
             .await
             .with_context(|| {
@@ -139,57 +378,49 @@ impl SheetsClient {
         Ok(token.as_ref().to_string())
     }
 
-    async fn resolve_sheet_id(
+    /// (title, sheetId) of every tab, in spreadsheet order.
+    async fn fetch_sheet_tabs(
         http: &reqwest::Client,
         token: &str,
         spreadsheet_id: &str,
-        sheet_name: &str,
-    ) -> Result<i32> {
+    ) -> Result<Vec<(String, i32)>> {
         let url = format!(
             "https://sheets.googleapis.com/v4/spreadsheets/{spreadsheet_id}?fields=sheets.properties"
         );
 
-        let meta: SpreadsheetMeta = http
-            .get(&url)
-            .bearer_auth(token)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let meta: SpreadsheetMeta =
+            Self::send_with_retry("metadata read", false, || http.get(&url).bearer_auth(token))
+                .await?
+                .json()
+                .await?;
 
-        meta.sheets
+        Ok(meta
+            .sheets
             .unwrap_or_default()
             .into_iter()
-            .find(|s| s.properties.title == sheet_name)
-            .map(|s| s.properties.sheet_id)
-            .with_context(|| format!("Could not find sheet tab named '{sheet_name}'"))
+            .map(|s| (s.properties.title, s.properties.sheet_id))
+            .collect())
     }
 
     /// Read values using service account (no API key)
     pub async fn fetch_sheet_values(&self, range_a1: &str) -> Result<Vec<Vec<String>>> {
-        let token = self.bearer_token().await?;
         let url = format!(
             "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
             self.spreadsheet_id, range_a1
         );
 
-        let body: SheetValuesResponse = self.http
-            .get(&url)
-            .bearer_auth(token)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let body: SheetValuesResponse =
+            self.send_authed("values read", false, |token| self.http.get(&url).bearer_auth(token))
+                .await?
+                .json()
+                .await?;
 
         Ok(body.values.unwrap_or_default())
     }
 
     /// Read a single cell using service account
-    #[allow(dead_code)]
     pub async fn read_cell_value(&self, row: usize, col: usize) -> Result<String> {
-        let token = self.bearer_token().await?;
+        validate_cell_coords(row, col)?;
         let col_letter = column_index_to_letter(col);
         let cell_range = format!("{}!{}{}", self.sheet_name, col_letter, row);
 
@@ -198,14 +429,11 @@ impl SheetsClient {
             self.spreadsheet_id, cell_range
         );
 
-        let body: SheetValuesResponse = self.http
-            .get(&url)
-            .bearer_auth(token)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let body: SheetValuesResponse =
+            self.send_authed("cell read", false, |token| self.http.get(&url).bearer_auth(token))
+                .await?
+                .json()
+                .await?;
 
         let value = body.values
             .and_then(|v| v.into_iter().next())
@@ -223,82 +451,118 @@ impl SheetsClient {
         value: &str,
         color: (u8, u8, u8),
     ) -> Result<()> {
-        if row == 0 || col == 0 {
-            anyhow::bail!("row/col must be 1-based (>= 1)");
-        }
+        self.update_cell_with_note(row, col, value, color, None).await
+    }
 
-        let token = self.bearer_token().await?;
+    /// Like `update_cell_value_and_color`, also setting the cell's hover note when `note`
+    /// is Some.
+    pub async fn update_cell_with_note(
+        &self,
+        row: usize, // 1-based
+        col: usize, // 1-based
+        value: &str,
+        color: (u8, u8, u8),
+        note: Option<&str>,
+    ) -> Result<()> {
+        self.update_cells_batch(&[CellUpdate {
+            row,
+            col,
+            value: value.to_string(),
+            color,
+            note: note.map(str::to_string),
+        }])
+        .await
+    }
 
-        let batch_update = serde_json::json!({
-            "requests": [{
-                "updateCells": {
-                    "range": {
-                        "sheetId": self.sheet_id,
-                        "startRowIndex": row - 1,
-                        "endRowIndex": row,
-                        "startColumnIndex": col - 1,
-                        "endColumnIndex": col
-                    },
-                    "rows": [{
-                        "values": [{
-                            "userEnteredValue": { "stringValue": value },
-                            "userEnteredFormat": {
-                                "backgroundColor": {
-                                    "red":   color.0 as f64 / 255.0,
-                                    "green": color.1 as f64 / 255.0,
-                                    "blue":  color.2 as f64 / 255.0
-                                }
-                            }
-                        }]
-                    }],
-                    "fields": "userEnteredValue,userEnteredFormat.backgroundColor"
+    /// Write several cells (value + background color) in one batchUpdate call,
+    /// with a single (cached) token. All coordinates are validated before anything is sent.
+    pub async fn update_cells_batch(&self, updates: &[CellUpdate]) -> Result<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        for u in updates {
+            validate_cell_coords(u.row, u.col)?;
+        }
+
+        let requests: Vec<serde_json::Value> = updates
+            .iter()
+            .map(|u| {
+                let mut cell = serde_json::json!({
+                    "userEnteredValue": { "stringValue": u.value },
+                    "userEnteredFormat": {
+                        "backgroundColor": {
+                            "red":   u.color.0 as f64 / 255.0,
+                            "green": u.color.1 as f64 / 255.0,
+                            "blue":  u.color.2 as f64 / 255.0
+                        }
+                    }
+                });
+                let mut fields = "userEnteredValue,userEnteredFormat.backgroundColor".to_string();
+                if let Some(note) = &u.note {
+                    cell["note"] = serde_json::json!(note);
+                    fields.push_str(",note");
                 }
-            }]
-        });
+                serde_json::json!({
+                    "updateCells": {
+                        "range": {
+                            "sheetId": self.sheet_id,
+                            "startRowIndex": u.row - 1,
+                            "endRowIndex": u.row,
+                            "startColumnIndex": u.col - 1,
+                            "endColumnIndex": u.col
+                        },
+                        "rows": [{ "values": [cell] }],
+                        "fields": fields
+                    }
+                })
+            })
+            .collect();
+        let batch_update = serde_json::json!({ "requests": requests });
 
         let url = format!(
             "https://sheets.googleapis.com/v4/spreadsheets/{}/:batchUpdate",
             self.spreadsheet_id
         );
 
-        // Retry on 429 (rate limit) with backoff; also throttle requests globally.
-        let mut attempt: u32 = 0;
-        loop {
-            attempt += 1;
-            Self::throttle_write_request().await;
-
-            let resp = self
-                .http
-                .post(&url)
-                .bearer_auth(&token)
-                .json(&batch_update)
-                .send()
-                .await?;
+        // Throttle writes globally; transient failures are retried with backoff.
+        self.send_authed("batchUpdate", true, |token| {
+            self.http.post(&url).bearer_auth(token).json(&batch_update)
+        })
+        .await?;
 
-            if resp.status().is_success() {
-                return Ok(());
-            }
+        Ok(())
+    }
+}
 
-            // Include body: Google puts the real reason there (permissions, invalid sheetId, etc.)
-            let status = resp.status();
-            let retry_after_secs = resp
-                .headers()
-                .get(reqwest::header::RETRY_AFTER)
-                .and_then(|v| v.to_str().ok())
-                .and_then(|s| s.parse::<u64>().ok());
-            let body = resp.text().await.unwrap_or_default();
+/// Backoff for a retryable Sheets failure (`attempt` is 1-based): honor Retry-After when
+/// present (same parsing as the OpenAI client), otherwise exponential (1s, 2s, 4s... capped
+/// at 64s) plus up to 50% jitter, so retries that fire close together don't stay in lockstep.
+fn compute_backoff_ms(headers: &reqwest::header::HeaderMap, attempt: u32) -> u64 {
+    if let Some(ms) = crate::openai_client::extract_wait_ms_from_headers(headers) {
+        return ms.min(120_000);
+    }
+    let base = 1000u64 * 2u64.pow(attempt.saturating_sub(1).min(6));
+    base + crate::mouse::jitter_between(0, base / 2)
+}
 
-            if status == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < 8 {
-                // Exponential backoff, but honor Retry-After if present.
-                let backoff_secs = 2u64.pow((attempt - 1).min(6));
-                let sleep_secs = retry_after_secs.unwrap_or(backoff_secs).min(120);
-                tokio::time::sleep(std::time::Duration::from_secs(sleep_secs)).await;
-                continue;
-            }
+/// "'Tax 2023'!A1:Z1000" -> "Tax 2023"; a range without a sheet prefix maps to itself.
+fn sheet_name_of_range(range: &str) -> String {
+    let name = range.rsplit_once('!').map_or(range, |(sheet, _)| sheet);
+    match name.strip_prefix('\'').and_then(|n| n.strip_suffix('\'')) {
+        Some(quoted) => quoted.replace("''", "'"),
+        None => name.to_string(),
+    }
+}
 
-            anyhow::bail!("Sheets batchUpdate failed: {status} body={body}");
-        }
+/// Sheets coordinates are 1-based; 0 would yield an empty column letter or row "0"
+/// and an invalid A1 range / negative grid index.
+fn validate_cell_coords(row: usize, col: usize) -> Result<()> {
+    if row == 0 || col == 0 {
+        anyhow::bail!(
+            "invalid sheet cell row={row} col={col}: row and col are 1-based and must be >= 1"
+        );
     }
+    Ok(())
 }
 
 /// 1 -> A, 2 -> B, ..., 26 -> Z, 27 -> AA ...
@@ -310,4 +574,35 @@ pub fn column_index_to_letter(mut col: usize) -> String {
         col /= 26;
     }
     result
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_letters() {
+        assert_eq!(column_index_to_letter(1), "A");
+        assert_eq!(column_index_to_letter(26), "Z");
+        assert_eq!(column_index_to_letter(27), "AA");
+        assert_eq!(column_index_to_letter(702), "ZZ");
+    }
+
+    #[test]
+    fn zero_row_or_col_is_rejected() {
+        assert!(validate_cell_coords(0, 1).is_err());
+        assert!(validate_cell_coords(1, 0).is_err());
+        assert!(validate_cell_coords(1, 1).is_ok());
+    }
+    #[test]
+    fn backoff_doubles_per_attempt_and_honors_retry_after() {
+        let none = reqwest::header::HeaderMap::new();
+        for (attempt, base) in [(1, 1000), (2, 2000), (4, 8000), (20, 64_000)] {
+            let ms = compute_backoff_ms(&none, attempt);
+            assert!((base..=base + base / 2).contains(&ms), "attempt {attempt}: {ms}ms");
+        }
+        let mut retry_after = reqwest::header::HeaderMap::new();
+        retry_after.insert(reqwest::header::RETRY_AFTER, "5".parse().unwrap());
+        assert_eq!(compute_backoff_ms(&retry_after, 3), 5000);
+    }
+}
@@ -0,0 +1,253 @@
+// src/steplog.rs
+//
+// Lightweight run logging. LOG_FORMAT=text (default) keeps the emoji lines for
+// interactive runs; LOG_FORMAT=json emits one JSON object per line for log shipping.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Log a free-form message at info level (text: as-is, json: {"level":"info","msg":...}).
+macro_rules! log_info {
+    ($($arg:tt)*) => { $crate::steplog::emit("info", &format!($($arg)*)) };
+}
+
+/// Log a free-form message at warn level (text mode writes to stderr).
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::steplog::emit("warn", &format!($($arg)*)) };
+}
+
+/// Sheet "Comment" of the client being processed, attached to its step records.
+static CLIENT_COMMENT: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_client_comment(comment: Option<String>) {
+    *CLIENT_COMMENT.lock().unwrap() = comment;
+}
+
+/// Most recent failure reason in the current client (an `on_fail=continue` assertion or a
+/// Branch answered "no"), for `UpdateSheetCell`'s `{reason}` note placeholder.
+static LAST_FAILURE: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_last_failure(reason: Option<String>) {
+    *LAST_FAILURE.lock().unwrap() = reason;
+}
+
+pub fn last_failure() -> Option<String> {
+    LAST_FAILURE.lock().unwrap().clone()
+}
+
+/// File-name tag of the running step: "07" for top-level step 7, "07-b2" for the second
+/// step a Branch in it ran (CURRENT_STEP_TAG, set by the step loop and Branch), so nested
+/// steps don't overwrite their parent's per-step artifacts.
+pub fn step_tag() -> String {
+    std::env::var("CURRENT_STEP_TAG").unwrap_or_else(|_| {
+        let n: usize = std::env::var("CURRENT_STEP_NO").ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+        format!("{n:02}")
+    })
+}
+
+pub fn json_enabled() -> bool {
+    std::env::var("LOG_FORMAT").map_or(false, |v| v.eq_ignore_ascii_case("json"))
+}
+
+fn now_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+pub fn emit(level: &str, msg: &str) {
+    if json_enabled() {
+        let line = serde_json::json!({ "ts_ms": now_ms(), "level": level, "msg": msg });
+        println!("{line}");
+    } else if level == "info" {
+        println!("{msg}");
+    } else {
+        eprintln!("{msg}");
+    }
+}
+
+/// Outcome of a validation question asked during a step.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationRecord {
+    pub question: String,
+    pub answer: bool,
+    pub confidence: Option<f32>,
+    pub reasoning: Option<String>,
+}
+
+/// Extra facts a step reports back to the loop (beyond success/failure).
+#[derive(Debug, Clone, Default)]
+pub struct StepDetails {
+    pub validation: Option<ValidationRecord>,
+    /// A DOM click/hover picked its target by heuristic after the LLM decision failed
+    pub used_fallback: bool,
+    /// Results of the steps a Branch ran, in order
+    pub nested: Vec<StepResult>,
+}
+
+/// What `execute_step` hands back to the loop for the run report.
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    /// Answer of the step's validation question, if it asked one
+    pub validation: Option<bool>,
+    pub used_fallback: bool,
+    /// Steps run inside this one (a Branch's chosen arm); recorded like top-level steps
+    pub nested: Vec<StepResult>,
+}
+
+/// One record per executed step.
+#[derive(Debug, Serialize)]
+pub struct StepRecord<'a> {
+    pub step_no: usize,
+    pub step_type: &'a str,
+    pub outcome: &'a str, // "ok" | "error" | "stop_client" | "abort"
+    pub duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<&'a ValidationRecord>,
+}
+
+pub fn log_step(rec: &StepRecord) {
+    let comment = CLIENT_COMMENT.lock().unwrap().clone();
+    if json_enabled() {
+        let mut v = serde_json::to_value(rec).unwrap_or_default();
+        if let Some(obj) = v.as_object_mut() {
+            obj.insert("ts_ms".into(), serde_json::json!(now_ms()));
+            obj.insert("level".into(), serde_json::json!("step"));
+            if let Some(c) = &comment {
+                obj.insert("client_comment".into(), serde_json::json!(c));
+            }
+        }
+        println!("{v}");
+        return;
+    }
+
+    let icon = match rec.outcome {
+        "ok" => "✔",
+        "error" => "❌",
+        _ => "⏹️",
+    };
+    let note = comment.map(|c| format!(" 💬 {c}")).unwrap_or_default();
+    println!(
+        "{icon} step {} {} {} ({}ms){note}",
+        rec.step_no, rec.step_type, rec.outcome, rec.duration_ms
+    );
+    if let Some(v) = rec.validation {
+        println!(
+            "   validation: {:?} → {} (confidence {:.2})",
+            v.question,
+            v.answer,
+            v.confidence.unwrap_or(0.0)
+        );
+    }
+    if let Some(e) = &rec.error {
+        eprintln!("   error: {e}");
+    }
+}
+
+/// Per-client slice of the run report, opened by each BeginClient.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientReport {
+    pub row: usize,
+    pub steps: usize,
+    pub validations_failed: usize,
+    /// The client was cut short by StopClient
+    pub stopped: bool,
+    pub success: bool,
+}
+
+/// End-of-run summary, printed by main and written to RUN_DIR/report.json with SAVE_REPORT=1.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunReport {
+    pub total_steps: usize,
+    pub validations_passed: usize,
+    pub validations_failed: usize,
+    pub heuristic_fallbacks: usize,
+    pub llm_requests: usize,
+    pub wall_clock_ms: u128,
+    /// Why the run ended early, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aborted: Option<String>,
+    /// Last lines of RUN_DIR/chromedriver.log, captured when the run aborted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chromedriver_log_tail: Vec<String>,
+    pub clients: Vec<ClientReport>,
+}
+
+impl RunReport {
+    pub fn begin_client(&mut self, row: usize) {
+        self.clients.push(ClientReport {
+            row,
+            steps: 0,
+            validations_failed: 0,
+            stopped: false,
+            success: true,
+        });
+    }
+
+    pub fn record(&mut self, r: &StepResult) {
+        self.total_steps += 1;
+        if r.used_fallback {
+            self.heuristic_fallbacks += 1;
+        }
+        match r.validation {
+            Some(true) => self.validations_passed += 1,
+            Some(false) => self.validations_failed += 1,
+            None => {}
+        }
+        if let Some(c) = self.clients.last_mut() {
+            c.steps += 1;
+            if r.validation == Some(false) {
+                c.validations_failed += 1;
+                c.success = false;
+            }
+        }
+        for n in &r.nested {
+            self.record(n);
+        }
+    }
+
+    pub fn stop_client(&mut self) {
+        self.total_steps += 1;
+        if let Some(c) = self.clients.last_mut() {
+            c.steps += 1;
+            c.stopped = true;
+            c.success = false;
+        }
+    }
+
+    pub fn print(&self) {
+        if json_enabled() {
+            let mut v = serde_json::to_value(self).unwrap_or_default();
+            if let Some(obj) = v.as_object_mut() {
+                obj.insert("ts_ms".into(), serde_json::json!(now_ms()));
+                obj.insert("level".into(), serde_json::json!("report"));
+            }
+            println!("{v}");
+            return;
+        }
+
+        let ok = self.clients.iter().filter(|c| c.success).count();
+        println!("📊 Run summary");
+        println!(
+            "   steps: {}  validations: {} passed / {} failed  heuristic fallbacks: {}",
+            self.total_steps, self.validations_passed, self.validations_failed, self.heuristic_fallbacks
+        );
+        println!(
+            "   OpenAI requests: {}  wall clock: {:.1}s",
+            self.llm_requests,
+            self.wall_clock_ms as f64 / 1000.0
+        );
+        println!("   clients: {ok}/{} succeeded", self.clients.len());
+        for c in self.clients.iter().filter(|c| !c.success) {
+            let why = if c.stopped { "stopped" } else { "validation failed" };
+            println!("     row {}: {why} ({} failed validations)", c.row, c.validations_failed);
+        }
+        if let Some(reason) = &self.aborted {
+            eprintln!("   aborted: {reason}");
+        }
+    }
+}